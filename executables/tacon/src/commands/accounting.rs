@@ -1,5 +1,7 @@
 use tacacsrs_messages::accounting::reply::AccountingReply;
 use tacacsrs_messages::accounting::request::AccountingRequest;
+use tacacsrs_messages::arguments::argument::Argument;
+use tacacsrs_messages::arguments::dictionary::{CMD, CMD_ARG, SERVICE};
 use tacacsrs_messages::enumerations::*;
 use tacacsrs_networking::session::Session;
 use tacacsrs_networking::sessions::accounting_session::AccountingSessionTrait;
@@ -9,16 +11,19 @@ pub async fn send_accounting_request(
     user: &str,
     port: &str,
     rem_address: &str,
-    cmd: &String,
+    cmd: &str,
     cmd_args: &Option<Vec<String>>,
 ) -> anyhow::Result<AccountingReply> {
-    let args = vec!["service=shell".to_string(), format!("cmd={}", cmd)].into_iter()
+    let args = [
+        Argument::mandatory(SERVICE.name, "shell"),
+        Argument::mandatory(CMD.name, cmd),
+    ].into_iter()
         .chain(
             cmd_args
                 .as_ref()
                 .unwrap_or(&Vec::new())
                 .iter()
-                .map(|arg| format!("cmd-arg={}", arg)),
+                .map(|arg| Argument::mandatory(CMD_ARG.name, arg.as_str())),
         )
         .collect();
 
@@ -28,9 +33,9 @@ pub async fn send_accounting_request(
         priv_lvl: 0,
         authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
         authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
-        user: user.to_owned(),
-        port: port.to_owned(),
-        rem_address: rem_address.to_owned(),
+        user: user.into(),
+        port: port.into(),
+        rem_address: rem_address.into(),
         args,
     };
 
@@ -0,0 +1,31 @@
+use tacacsrs_messages::authentication::reply::AuthenticationReply;
+use tacacsrs_messages::authentication::start::AuthenticationStart;
+use tacacsrs_messages::enumerations::*;
+use tacacsrs_networking::prompter::TerminalPrompter;
+use tacacsrs_networking::session::Session;
+use tacacsrs_networking::sessions::authentication_session::AuthenticationSessionTrait;
+
+pub async fn send_authentication_request(
+    session: &Session,
+    user: &str,
+    port: &str,
+    rem_address: &str,
+) -> anyhow::Result<AuthenticationReply> {
+    let authentication_start = AuthenticationStart {
+        action: TacacsAuthenticationAction::TacPlusAuthenLogin,
+        priv_lvl: 1,
+        authen_type: TacacsAuthenticationType::TacPlusAuthenTypeAscii,
+        authen_service: TacacsAuthenticationService::TacPlusAuthenSvcLogin,
+        user: user.into(),
+        port: port.into(),
+        rem_address: rem_address.into(),
+        data: "".into(),
+    };
+
+    let response = session.authenticate(authentication_start, &TerminalPrompter::new()).await
+        .map_err(|e| anyhow::Error::msg(format!("Failed to authenticate: {}", e)))?;
+
+    println!("Received authentication response: {:?}", response);
+
+    Ok(response)
+}
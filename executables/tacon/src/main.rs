@@ -1,14 +1,16 @@
 mod commands;
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
-use clap::{arg, Parser, Subcommand};
+use clap::{Parser, Subcommand};
 use commands::accounting::send_accounting_request;
+use commands::authentication::send_authentication_request;
 use tacacsrs_networking::{
+    codec::DEFAULT_MAX_BODY_LENGTH,
+    connection::Connection,
     helpers::TlsConfigurationBuilder, 
-    tcp_connection::{TcpConnection, TcpConnectionTrait}, 
-    tls_connection::{TLSConnectionTrait, TlsConnection},
     traits::SessionManagementTrait};
 
 
@@ -37,6 +39,12 @@ pub struct Cli {
     #[arg(long, value_name = "CLIENT_KEY", help = "The client key to use for TLS")]
     client_key: Option<String>,
 
+    #[arg(long, default_value_t = DEFAULT_MAX_BODY_LENGTH, help = "The largest packet body accepted from the server")]
+    max_body_length: usize,
+
+    #[arg(long, value_name = "SECONDS", help = "Give up on a request if the server has not replied within this many seconds")]
+    reply_timeout: Option<u64>,
+
     #[arg(short, long, action = clap::ArgAction::Count, help = "Increase verbosity")]
     verbose: u8,
 
@@ -76,12 +84,6 @@ enum Commands {
     Authorization,
 }
 
-enum Connection {
-    TcpConnection(Arc<TcpConnection>),
-    TlsConnection(Arc<TlsConnection>),
-    
-}
-
 pub async fn run(cli: Cli) -> anyhow::Result<()> {
     println!("Running with verbose level: {}", cli.verbose);
     if cli.verbose > 0 {
@@ -119,7 +121,13 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
     let obfuscation_key = cli.obfuscation_key.map(|key| key.to_owned().into_bytes());
     
     let tcp_connection = tacacsrs_networking::helpers::connect_tcp(&cli.server_addr).await?;
-    let tacacs_connection : Connection = if cli.use_tls {
+    let tacacs_connection = Arc::new(
+        Connection::new(obfuscation_key.as_deref())
+            .with_max_body_length(cli.max_body_length)
+            .with_reply_timeout(cli.reply_timeout.map(Duration::from_secs))
+    );
+
+    if cli.use_tls {
         let client_certificate = cli.client_certificate.unwrap();
         let client_key = cli.client_key.unwrap();
         
@@ -131,30 +139,14 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
         let tls_connection = tacacsrs_networking::helpers::connect_tls(
             &tls_config, tcp_connection, "tacacsserver.local").await?;
 
-        let tacacs_connection = Arc::new(
-            tacacsrs_networking::tls_connection::TlsConnection::new(obfuscation_key.as_deref())
-        );
-
         tacacs_connection.run(tls_connection).await?;
-
-        Connection::TlsConnection(tacacs_connection)
     }
     else 
     {
-        let tacacs_connection = Arc::new(
-            tacacsrs_networking::tcp_connection::TcpConnection::new(obfuscation_key.as_deref())
-        );
-
         tacacs_connection.run(tcp_connection).await?;
+    }
 
-        Connection::TcpConnection(tacacs_connection)
-    };
-
-
-    let session = match tacacs_connection {
-        Connection::TcpConnection(tcp_connection) => tcp_connection.create_session().await?,
-        Connection::TlsConnection(tls_connection) => tls_connection.create_session().await?,
-    };
+    let session = tacacs_connection.create_session().await?;
 
     if let Some(command) = &cli.command {
         println!("Running command: {:?}", command);
@@ -183,7 +175,17 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
                 }
             }
             Commands::Authentication => {
-                println!("Authentication");
+                let user = cli.user.as_deref().unwrap_or_default();
+                let port = cli
+                    .port
+                    .as_ref()
+                    .ok_or_else(|| anyhow::Error::msg("Port is required"))?;
+                let rem_addr = cli
+                    .rem_addr
+                    .as_ref()
+                    .ok_or_else(|| anyhow::Error::msg("Remote address is required"))?;
+
+                send_authentication_request(&session, user, port, rem_addr).await?;
             }
             Commands::Authorization => {
                 println!("Authorization");
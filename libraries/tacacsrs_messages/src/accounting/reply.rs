@@ -1,9 +1,8 @@
 use std::io::Cursor;
-use byteorder::{BigEndian, ReadBytesExt};
-use num_enum::TryFromPrimitive;
-use crate::{constants::TACACS_ACCOUNTING_REPLY_MIN_LENGTH, helpers::read_string, traits::TacacsBodyTrait};
+use crate::tacacs_string::TacacsString;
+use crate::{constants::TACACS_ACCOUNTING_REPLY_MIN_LENGTH, helpers::{length_u16, read_enum, read_string, read_u16}, traits::TacacsBodyTrait};
 use crate::packet::{Packet, PacketTrait};
-use anyhow::Context;
+use crate::error::TacacsError;
 use crate::enumerations::TacacsAccountingStatus;
 
 // 1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8
@@ -16,92 +15,61 @@ use crate::enumerations::TacacsAccountingStatus;
 // +----------------+
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct AccountingReply {
     pub status: TacacsAccountingStatus,
-    pub server_msg: String,
-    pub data: String,
+    pub server_msg: TacacsString,
+    pub data: TacacsString,
 }
 
 impl AccountingReply {
-    pub fn from_packet(packet: &Packet) -> Result<Self, anyhow::Error> {
-        let expected_length = Self::size_from_bytes(packet.body()).with_context(|| "Unable to determine expected length of packet")?;
+    pub fn from_packet(packet: &Packet) -> Result<Self, TacacsError> {
+        let expected_length = Self::size_from_bytes(packet.body())?;
         if packet.body().len() < expected_length {
-            return Err(anyhow::Error::msg(format!("Packet body length does not match expected length. Expected: {}, Actual: {}", expected_length, packet.body().len())));
+            return Err(TacacsError::BodyLengthMismatch { expected: expected_length, actual: packet.body().len() });
         }
 
-        match Self::from_bytes(packet.body()).with_context(|| "Unable to convert packet body to Reply") {
-            Ok(reply) => Ok(reply),
-            Err(err) => Err(err),
-        }
+        Self::from_bytes(packet.body())
     }
 
-    fn size_from_bytes(data : &[u8]) -> Result<usize, anyhow::Error> {       
+    fn size_from_bytes(data : &[u8]) -> Result<usize, TacacsError> {
         let mut cursor = Cursor::new(data);
 
-        let server_msg_len = match cursor.read_u16::<BigEndian>().with_context(|| "Unable to read server_msg_len") {
-            Ok(len) => len as usize,
-            Err(err) => return Err(err),
-        };
-
-        let data_len = match cursor.read_u16::<BigEndian>().with_context(|| "Unable to read data_len") {
-            Ok(len) => len as usize,
-            Err(err) => return Err(err),
-        };
+        let server_msg_len = read_u16(&mut cursor, "server_msg_len")? as usize;
+        let data_len = read_u16(&mut cursor, "data_len")? as usize;
 
         Ok(TACACS_ACCOUNTING_REPLY_MIN_LENGTH + server_msg_len + data_len)
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TacacsError> {
         let mut cursor = Cursor::new(bytes);
 
-        let server_msg_len = match cursor.read_u16::<BigEndian>().with_context(|| "Unable to read server_msg_len") {
-            Ok(len) => len as usize,
-            Err(err) => return Err(err),
-        };
+        let server_msg_len = read_u16(&mut cursor, "server_msg_len")? as usize;
 
-        let data_len = match cursor.read_u16::<BigEndian>().with_context(|| "Unable to read data_len") {
-            Ok(len) => len as usize,
-            Err(err) => return Err(err),
-        };
+        let data_len = read_u16(&mut cursor, "data_len")? as usize;
 
-        let status = match cursor.read_u8().with_context(|| "Unable to read status") {
-            Ok(status) => TacacsAccountingStatus::try_from_primitive(status).with_context(|| "Unable to convert status to TacacsAccountingStatus")?,
-            Err(err) => return Err(err),
-        };
+        let status = read_enum::<TacacsAccountingStatus>(&mut cursor, "status")?;
 
-        let server_msg = match read_string(&mut cursor, server_msg_len).with_context(|| "Unable to read server_msg") {
-            Ok(msg) => msg,
-            Err(err) => return Err(err),
-        };
+        let server_msg = read_string(&mut cursor, server_msg_len, "server_msg")?;
 
-        let data = match read_string(&mut cursor, data_len).with_context(|| "Unable to read data") {
-            Ok(data) => data,
-            Err(err) => return Err(err),
-        };
+        let data = read_string(&mut cursor, data_len, "data")?;
 
         Ok(AccountingReply{status, server_msg, data})
     }
-
-    
 }
 
 
 impl TacacsBodyTrait for AccountingReply
 {
-    fn to_bytes(&self) -> Vec<u8> {
-        let bytes = vec![
-            (self.server_msg.len() >> 8) as u8,
-            self.server_msg.len() as u8,
-            (self.data.len() >> 8) as u8,
-            self.data.len() as u8,
-            self.status as u8,
-        ];
-
-        let mut bytes = bytes;
+    fn try_to_bytes(&self) -> Result<Vec<u8>, TacacsError> {
+        let mut bytes = Vec::with_capacity(TACACS_ACCOUNTING_REPLY_MIN_LENGTH + self.server_msg.len() + self.data.len());
+        bytes.extend(length_u16(self.server_msg.len(), "server_msg")?);
+        bytes.extend(length_u16(self.data.len(), "data")?);
+        bytes.push(self.status as u8);
+
         bytes.extend(self.server_msg.as_bytes());
         bytes.extend(self.data.as_bytes());
-        bytes
+        Ok(bytes)
     }
 }
 
@@ -151,9 +119,7 @@ pub mod tests
         assert!(reply.is_err());
         
         let error = reply.unwrap_err();
-        assert!(
-            error.to_string().contains("Unable to convert status to TacacsAccountingStatus"),
-            "Actual Error: {}", error);
+        assert_eq!(error, TacacsError::InvalidValue { field: "status", offset: 4, value: 0xff });
     }
 
     #[test]
@@ -164,9 +130,7 @@ pub mod tests
         assert!(reply.is_err());
 
         let error = reply.unwrap_err();
-        assert!(
-            error.to_string().contains("Unable to read data"),
-            "Actual Error: {}", error);
+        assert_eq!(error, TacacsError::TooShort { field: "data", offset: 15, needed: 19, got: 18 });
     }
 
     #[test]
@@ -174,7 +138,17 @@ pub mod tests
         let bytes = generate_accounting_reply_data();
         let reply = AccountingReply::from_bytes(&bytes).unwrap();
 
-        assert_eq!(reply.to_bytes(), bytes);
+        assert_eq!(reply.try_to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_reply_to_bytes_server_msg_too_long() {
+        let bytes = generate_accounting_reply_data();
+        let mut reply = AccountingReply::from_bytes(&bytes).unwrap();
+        reply.server_msg = TacacsString::from(vec![b'x'; 65536]);
+
+        let error = reply.try_to_bytes().unwrap_err();
+        assert_eq!(error, TacacsError::FieldTooLong { field: "server_msg", max: 65535, actual: 65536 });
     }
 
     #[test]
@@ -225,6 +199,8 @@ pub mod tests
         let reply = AccountingReply::from_packet(&packet);
 
         assert!(reply.is_err());
-        assert!(reply.unwrap_err().to_string().contains("Packet body length does not match expected length"));
+        assert_eq!(reply.unwrap_err(), TacacsError::BodyLengthMismatch { expected: 5 + 0xff0a + 4, actual: 19 });
     }
+
+    crate::helpers::body_decoding_proptests!(AccountingReply, generate_accounting_reply_data);
 }
@@ -0,0 +1,320 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::ops::{Index, IndexMut};
+
+use crate::arguments::dictionary::{lookup, Attribute, AttributeValueType};
+use crate::error::TacacsError;
+use crate::tacacs_string::TacacsString;
+
+const MANDATORY_SEPARATOR: u8 = b'=';
+const OPTIONAL_SEPARATOR: u8 = b'*';
+
+/// An attribute-value pair. Mandatory arguments are encoded as `name=value`,
+/// optional ones as `name*value`. The bytes are kept exactly as they were
+/// received, so a malformed argument still decodes and re-encodes unchanged;
+/// the split into name and value is only checked when it is asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Argument(TacacsString);
+
+impl Argument {
+    pub fn mandatory(name: impl Into<TacacsString>, value: impl Into<TacacsString>) -> Self {
+        Self::encode(name.into(), MANDATORY_SEPARATOR, value.into())
+    }
+
+    pub fn optional(name: impl Into<TacacsString>, value: impl Into<TacacsString>) -> Self {
+        Self::encode(name.into(), OPTIONAL_SEPARATOR, value.into())
+    }
+
+    fn encode(name: TacacsString, separator: u8, value: TacacsString) -> Self {
+        let mut bytes = Vec::with_capacity(name.len() + 1 + value.len());
+        bytes.extend(name.as_bytes());
+        bytes.push(separator);
+        bytes.extend(value.as_bytes());
+        Argument(TacacsString::from(bytes))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Argument(TacacsString::from_bytes(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.as_bytes().to_vec()
+    }
+
+    pub fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Finds the first `=` or `*`, which must follow a non-empty name.
+    fn separator(&self) -> Result<usize, TacacsError> {
+        let bytes = self.0.as_bytes();
+        match bytes.iter().position(|byte| *byte == MANDATORY_SEPARATOR || *byte == OPTIONAL_SEPARATOR) {
+            None => Err(self.malformed("missing '=' or '*' separator")),
+            Some(0) => Err(self.malformed("empty attribute name")),
+            Some(separator) => Ok(separator),
+        }
+    }
+
+    pub fn is_well_formed(&self) -> bool {
+        self.separator().is_ok()
+    }
+
+    pub fn name(&self) -> Result<&[u8], TacacsError> {
+        self.separator().map(|separator| &self.0.as_bytes()[..separator])
+    }
+
+    pub fn value(&self) -> Result<&[u8], TacacsError> {
+        self.separator().map(|separator| &self.0.as_bytes()[separator + 1..])
+    }
+
+    pub fn is_mandatory(&self) -> Result<bool, TacacsError> {
+        self.separator().map(|separator| self.0.as_bytes()[separator] == MANDATORY_SEPARATOR)
+    }
+
+    /// The dictionary entry for this argument, if it is a standard attribute.
+    pub fn attribute(&self) -> Option<&'static Attribute> {
+        self.name().ok().and_then(lookup)
+    }
+
+    fn value_str(&self) -> Result<&str, TacacsError> {
+        std::str::from_utf8(self.value()?).map_err(|_| self.malformed("value is not valid UTF-8"))
+    }
+
+    pub fn as_numeric(&self) -> Result<u64, TacacsError> {
+        self.value_str()?.parse()
+            .map_err(|_| self.invalid_value(AttributeValueType::Numeric))
+    }
+
+    pub fn as_boolean(&self) -> Result<bool, TacacsError> {
+        match self.value()? {
+            b"true" => Ok(true),
+            b"false" => Ok(false),
+            _ => Err(self.invalid_value(AttributeValueType::Boolean)),
+        }
+    }
+
+    pub fn as_ip_address(&self) -> Result<IpAddr, TacacsError> {
+        self.value_str()?.parse()
+            .map_err(|_| self.invalid_value(AttributeValueType::IpAddress))
+    }
+
+    /// Checks the argument is well formed and its value has the type the
+    /// dictionary gives the attribute. Attributes outside the dictionary only
+    /// need to be well formed.
+    pub fn validate(&self) -> Result<(), TacacsError> {
+        self.separator()?;
+
+        match self.attribute().map(|attribute| attribute.value_type) {
+            Some(AttributeValueType::Numeric) => self.as_numeric().map(|_| ()),
+            Some(AttributeValueType::Boolean) => self.as_boolean().map(|_| ()),
+            Some(AttributeValueType::IpAddress) => self.as_ip_address().map(|_| ()),
+            Some(AttributeValueType::String) | None => Ok(()),
+        }
+    }
+
+    fn malformed(&self, reason: &str) -> TacacsError {
+        TacacsError::InvalidArgument { argument: self.to_string(), reason: reason.to_string() }
+    }
+
+    fn invalid_value(&self, expected: AttributeValueType) -> TacacsError {
+        self.malformed(&format!("expected a {} value", expected))
+    }
+}
+
+impl fmt::Display for Argument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+
+/// The arguments of an authorization or accounting message, in wire order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArgumentList(Vec<Argument>);
+
+impl ArgumentList {
+    pub fn new() -> Self {
+        ArgumentList(Vec::new())
+    }
+
+    pub fn push(&mut self, argument: Argument) {
+        self.0.push(argument);
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Argument> {
+        self.0.iter()
+    }
+
+    /// The first argument with the given name.
+    pub fn get(&self, name: &str) -> Option<&Argument> {
+        self.0.iter().find(|argument| argument.name() == Ok(name.as_bytes()))
+    }
+
+    /// Every argument with the given name, such as each `cmd-arg`.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Argument> {
+        self.0.iter().filter(move |argument| argument.name() == Ok(name.as_bytes()))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Removes every argument with the given name.
+    pub fn remove(&mut self, name: &str) {
+        self.0.retain(|argument| argument.name() != Ok(name.as_bytes()));
+    }
+
+    pub fn get_string(&self, name: &str) -> Option<TacacsString> {
+        self.get(name).and_then(|argument| argument.value().ok()).map(TacacsString::from_bytes)
+    }
+
+    pub fn get_numeric(&self, name: &str) -> Result<Option<u64>, TacacsError> {
+        self.get(name).map(Argument::as_numeric).transpose()
+    }
+
+    pub fn get_boolean(&self, name: &str) -> Result<Option<bool>, TacacsError> {
+        self.get(name).map(Argument::as_boolean).transpose()
+    }
+
+    pub fn get_ip_address(&self, name: &str) -> Result<Option<IpAddr>, TacacsError> {
+        self.get(name).map(Argument::as_ip_address).transpose()
+    }
+
+    pub fn validate(&self) -> Result<(), TacacsError> {
+        self.0.iter().try_for_each(Argument::validate)
+    }
+}
+
+impl From<Vec<Argument>> for ArgumentList {
+    fn from(arguments: Vec<Argument>) -> Self {
+        ArgumentList(arguments)
+    }
+}
+
+impl FromIterator<Argument> for ArgumentList {
+    fn from_iter<I: IntoIterator<Item = Argument>>(iter: I) -> Self {
+        ArgumentList(iter.into_iter().collect())
+    }
+}
+
+impl Extend<Argument> for ArgumentList {
+    fn extend<I: IntoIterator<Item = Argument>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl Index<usize> for ArgumentList {
+    type Output = Argument;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for ArgumentList {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl IntoIterator for ArgumentList {
+    type Item = Argument;
+    type IntoIter = std::vec::IntoIter<Argument>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ArgumentList {
+    type Item = &'a Argument;
+    type IntoIter = std::slice::Iter<'a, Argument>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arguments::dictionary::{CMD_ARG, PRIV_LVL, SERVICE};
+
+    #[test]
+    fn test_from_bytes_splits_on_first_separator() {
+        let argument = Argument::from_bytes(b"cmd-arg=a=b*c");
+        assert_eq!(argument, Argument::mandatory("cmd-arg", "a=b*c"));
+        assert_eq!(argument.name().unwrap(), b"cmd-arg");
+        assert_eq!(argument.value().unwrap(), b"a=b*c");
+
+        let argument = Argument::from_bytes(b"idletime*10");
+        assert_eq!(argument, Argument::optional("idletime", "10"));
+        assert!(!argument.is_mandatory().unwrap());
+        assert_eq!(argument.to_bytes(), b"idletime*10");
+    }
+
+    #[test]
+    fn test_malformed_argument_round_trips() {
+        for bytes in [&b"service"[..], b"=shell", b""] {
+            let argument = Argument::from_bytes(bytes);
+
+            assert!(!argument.is_well_formed());
+            assert!(matches!(argument.name(), Err(TacacsError::InvalidArgument { .. })));
+            assert!(matches!(argument.value(), Err(TacacsError::InvalidArgument { .. })));
+            assert!(argument.validate().is_err());
+            assert_eq!(argument.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_list_skips_malformed_arguments() {
+        let arguments = ArgumentList::from(vec![
+            Argument::from_bytes(b"service"),
+            Argument::mandatory(SERVICE.name, "shell"),
+        ]);
+
+        assert_eq!(arguments.get_string("service").unwrap(), "shell");
+        assert!(arguments.validate().is_err());
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let arguments = ArgumentList::from(vec![
+            Argument::mandatory(SERVICE.name, "shell"),
+            Argument::mandatory(PRIV_LVL.name, "15"),
+            Argument::optional("noescape", "true"),
+            Argument::mandatory("addr", "192.0.2.1"),
+            Argument::mandatory(CMD_ARG.name, "interface"),
+            Argument::mandatory(CMD_ARG.name, "brief"),
+        ]);
+
+        assert_eq!(arguments.get_string("service").unwrap(), "shell");
+        assert_eq!(arguments.get_numeric("priv-lvl").unwrap(), Some(15));
+        assert_eq!(arguments.get_boolean("noescape").unwrap(), Some(true));
+        assert_eq!(arguments.get_ip_address("addr").unwrap(), Some("192.0.2.1".parse().unwrap()));
+        assert_eq!(arguments.get_numeric("timeout").unwrap(), None);
+        assert_eq!(arguments.get_all("cmd-arg").count(), 2);
+        assert!(arguments.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_mistyped_values() {
+        assert!(Argument::mandatory("timeout", "soon").validate().is_err());
+        assert!(Argument::mandatory("nohangup", "yes").validate().is_err());
+        assert!(Argument::mandatory("addr", "not-an-address").validate().is_err());
+        assert!(Argument::mandatory("cisco-av-pair", "anything").validate().is_ok());
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+
+/// The value types defined in RFC 8907 section 3.7.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeValueType {
+    String,
+    Numeric,
+    Boolean,
+    IpAddress,
+}
+
+impl fmt::Display for AttributeValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeValueType::String => write!(f, "string"),
+            AttributeValueType::Numeric => write!(f, "numeric"),
+            AttributeValueType::Boolean => write!(f, "boolean"),
+            AttributeValueType::IpAddress => write!(f, "IP address"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute {
+    pub name: &'static str,
+    pub value_type: AttributeValueType,
+}
+
+impl Attribute {
+    const fn new(name: &'static str, value_type: AttributeValueType) -> Self {
+        Attribute { name, value_type }
+    }
+}
+
+// RFC 8907 section 8.2: authorization attributes
+pub const SERVICE: Attribute = Attribute::new("service", AttributeValueType::String);
+pub const PROTOCOL: Attribute = Attribute::new("protocol", AttributeValueType::String);
+pub const CMD: Attribute = Attribute::new("cmd", AttributeValueType::String);
+pub const CMD_ARG: Attribute = Attribute::new("cmd-arg", AttributeValueType::String);
+pub const ACL: Attribute = Attribute::new("acl", AttributeValueType::Numeric);
+pub const INACL: Attribute = Attribute::new("inacl", AttributeValueType::String);
+pub const OUTACL: Attribute = Attribute::new("outacl", AttributeValueType::String);
+pub const ADDR: Attribute = Attribute::new("addr", AttributeValueType::IpAddress);
+pub const ADDR_POOL: Attribute = Attribute::new("addr-pool", AttributeValueType::String);
+pub const TIMEOUT: Attribute = Attribute::new("timeout", AttributeValueType::Numeric);
+pub const IDLETIME: Attribute = Attribute::new("idletime", AttributeValueType::Numeric);
+pub const AUTOCMD: Attribute = Attribute::new("autocmd", AttributeValueType::String);
+pub const NOESCAPE: Attribute = Attribute::new("noescape", AttributeValueType::Boolean);
+pub const NOHANGUP: Attribute = Attribute::new("nohangup", AttributeValueType::Boolean);
+pub const PRIV_LVL: Attribute = Attribute::new("priv-lvl", AttributeValueType::Numeric);
+
+// RFC 8907 section 8.3: accounting attributes
+pub const TASK_ID: Attribute = Attribute::new("task_id", AttributeValueType::String);
+pub const START_TIME: Attribute = Attribute::new("start_time", AttributeValueType::Numeric);
+pub const STOP_TIME: Attribute = Attribute::new("stop_time", AttributeValueType::Numeric);
+pub const ELAPSED_TIME: Attribute = Attribute::new("elapsed_time", AttributeValueType::Numeric);
+pub const TIMEZONE: Attribute = Attribute::new("timezone", AttributeValueType::String);
+pub const EVENT: Attribute = Attribute::new("event", AttributeValueType::String);
+pub const REASON: Attribute = Attribute::new("reason", AttributeValueType::String);
+pub const BYTES: Attribute = Attribute::new("bytes", AttributeValueType::Numeric);
+pub const BYTES_IN: Attribute = Attribute::new("bytes_in", AttributeValueType::Numeric);
+pub const BYTES_OUT: Attribute = Attribute::new("bytes_out", AttributeValueType::Numeric);
+pub const PAKS: Attribute = Attribute::new("paks", AttributeValueType::Numeric);
+pub const PAKS_IN: Attribute = Attribute::new("paks_in", AttributeValueType::Numeric);
+pub const PAKS_OUT: Attribute = Attribute::new("paks_out", AttributeValueType::Numeric);
+pub const STATUS: Attribute = Attribute::new("status", AttributeValueType::Numeric);
+pub const ERR_MSG: Attribute = Attribute::new("err_msg", AttributeValueType::String);
+
+pub const STANDARD_ATTRIBUTES: &[Attribute] = &[
+    SERVICE, PROTOCOL, CMD, CMD_ARG, ACL, INACL, OUTACL, ADDR, ADDR_POOL, TIMEOUT, IDLETIME,
+    AUTOCMD, NOESCAPE, NOHANGUP, PRIV_LVL, TASK_ID, START_TIME, STOP_TIME, ELAPSED_TIME,
+    TIMEZONE, EVENT, REASON, BYTES, BYTES_IN, BYTES_OUT, PAKS, PAKS_IN, PAKS_OUT, STATUS, ERR_MSG,
+];
+
+/// Finds a standard attribute by name. Vendor specific attributes are not in the
+/// dictionary and are treated as strings.
+pub fn lookup(name: &[u8]) -> Option<&'static Attribute> {
+    STANDARD_ATTRIBUTES.iter().find(|attribute| attribute.name.as_bytes() == name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup(b"priv-lvl"), Some(&PRIV_LVL));
+        assert_eq!(lookup(b"bytes_in").map(|attribute| attribute.value_type), Some(AttributeValueType::Numeric));
+        assert_eq!(lookup(b"cisco-av-pair"), None);
+    }
+}
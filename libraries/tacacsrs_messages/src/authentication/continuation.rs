@@ -0,0 +1,187 @@
+use std::io::Cursor;
+use crate::tacacs_string::TacacsString;
+use crate::{constants::TACACS_AUTHENTICATION_CONTINUE_MIN_LENGTH, helpers::{length_u16, read_flags, read_string, read_u16}, traits::TacacsBodyTrait};
+use crate::packet::{Packet, PacketTrait};
+use crate::error::TacacsError;
+use crate::enumerations::TacacsAuthenticationContinueFlags;
+
+// 1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8
+// +----------------+----------------+----------------+----------------+
+// |          user_msg len           |            data_len             |
+// +----------------+----------------+----------------+----------------+
+// |     flags      |  user_msg ...
+// +----------------+----------------+----------------+----------------+
+// |    data ...
+// +----------------+
+
+
+#[derive(Debug, Clone)]
+pub struct AuthenticationContinue {
+    pub user_msg: TacacsString,
+    pub data: TacacsString,
+    pub flags: TacacsAuthenticationContinueFlags,
+}
+
+impl AuthenticationContinue {
+    pub fn from_packet(packet: &Packet) -> Result<Self, TacacsError> {
+        let expected_length = Self::size_from_bytes(packet.body())?;
+        if packet.body().len() < expected_length {
+            return Err(TacacsError::BodyLengthMismatch { expected: expected_length, actual: packet.body().len() });
+        }
+
+        Self::from_bytes(packet.body())
+    }
+
+    fn size_from_bytes(data : &[u8]) -> Result<usize, TacacsError> {
+        let mut cursor = Cursor::new(data);
+
+        let user_msg_len = read_u16(&mut cursor, "user_msg_len")? as usize;
+        let data_len = read_u16(&mut cursor, "data_len")? as usize;
+
+        Ok(TACACS_AUTHENTICATION_CONTINUE_MIN_LENGTH + user_msg_len + data_len)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TacacsError> {
+        let mut cursor = Cursor::new(bytes);
+
+        let user_msg_len = read_u16(&mut cursor, "user_msg_len")? as usize;
+        let data_len = read_u16(&mut cursor, "data_len")? as usize;
+
+        let flags = read_flags::<TacacsAuthenticationContinueFlags>(&mut cursor, "flags")?;
+
+        let user_msg = read_string(&mut cursor, user_msg_len, "user_msg")?;
+        let data = read_string(&mut cursor, data_len, "data")?;
+
+        Ok(AuthenticationContinue{user_msg, data, flags})
+    }
+
+    pub fn abort(reason: &str) -> Self {
+        AuthenticationContinue {
+            user_msg: TacacsString::new(),
+            data: TacacsString::from(reason),
+            flags: TacacsAuthenticationContinueFlags::TAC_PLUS_CONTINUE_FLAG_ABORT,
+        }
+    }
+
+    pub fn is_abort(&self) -> bool {
+        self.flags.contains(TacacsAuthenticationContinueFlags::TAC_PLUS_CONTINUE_FLAG_ABORT)
+    }
+}
+
+
+impl TacacsBodyTrait for AuthenticationContinue
+{
+    fn try_to_bytes(&self) -> Result<Vec<u8>, TacacsError> {
+        let mut bytes = Vec::with_capacity(TACACS_AUTHENTICATION_CONTINUE_MIN_LENGTH + self.user_msg.len() + self.data.len());
+        bytes.extend(length_u16(self.user_msg.len(), "user_msg")?);
+        bytes.extend(length_u16(self.data.len(), "data")?);
+        bytes.push(self.flags.bits());
+
+        bytes.extend(self.user_msg.as_bytes());
+        bytes.extend(self.data.as_bytes());
+        Ok(bytes)
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests
+{
+    use crate::{
+        enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType},
+        header::Header
+    };
+
+    use super::*;
+
+    fn generate_authentication_continue_data() -> Vec<u8> {
+        let user_message_string = "password";
+        let data_string = "data";
+
+        let mut data : Vec<u8> = Vec::new();
+        data.extend((user_message_string.len() as u16).to_be_bytes()); // 0: user_msg_len
+        data.extend((data_string.len() as u16).to_be_bytes()); // 2: data_len
+        data.push(TacacsAuthenticationContinueFlags::empty().bits()); // 4: flags
+
+        data.extend(user_message_string.as_bytes());
+        data.extend(data_string.as_bytes());
+
+        data
+    }
+
+    #[test]
+    fn test_continue_from_bytes() {
+        let bytes = generate_authentication_continue_data();
+        let continuation = AuthenticationContinue::from_bytes(&bytes).unwrap();
+
+        assert_eq!(continuation.user_msg, "password");
+        assert_eq!(continuation.data, "data");
+        assert!(!continuation.is_abort());
+    }
+
+    #[test]
+    fn test_read_bytes_incorrect_flags() {
+        let mut data = generate_authentication_continue_data();
+        data[4] = 0xff;
+
+        let error = AuthenticationContinue::from_bytes(&data).unwrap_err();
+        assert_eq!(error, TacacsError::InvalidValue { field: "flags", offset: 4, value: 0xff });
+    }
+
+    #[test]
+    fn test_read_bytes_truncated() {
+        let data = generate_authentication_continue_data();
+
+        let error = AuthenticationContinue::from_bytes(&data[..data.len()-1]).unwrap_err();
+        assert_eq!(error, TacacsError::TooShort { field: "data", offset: 13, needed: 17, got: 16 });
+    }
+
+    #[test]
+    fn test_continue_to_bytes() {
+        let bytes = generate_authentication_continue_data();
+        let continuation = AuthenticationContinue::from_bytes(&bytes).unwrap();
+
+        assert_eq!(continuation.try_to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_abort_round_trip() {
+        let abort = AuthenticationContinue::abort("user cancelled");
+        let continuation = AuthenticationContinue::from_bytes(&abort.try_to_bytes().unwrap()).unwrap();
+
+        assert!(continuation.is_abort());
+        assert_eq!(continuation.data, "user cancelled");
+    }
+
+    #[test]
+    fn test_continue_size_from_bytes() {
+        let bytes = generate_authentication_continue_data();
+        let size = AuthenticationContinue::size_from_bytes(&bytes).unwrap();
+
+        assert_eq!(size, bytes.len());
+    }
+
+    #[test]
+    fn test_continue_from_packet_invalid_length() {
+        let mut data = generate_authentication_continue_data();
+        data[0] = 0xff; // first byte of user_msg_len is set to 0xff
+
+        let header = Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAuthentication,
+            seq_no: 3,
+            flags: TacacsFlags::empty(),
+            session_id: 0,
+            length: data.len() as u32,
+        };
+
+        let packet = Packet::new(header, data).unwrap();
+        let continuation = AuthenticationContinue::from_packet(&packet);
+
+        assert!(continuation.is_err());
+        assert_eq!(continuation.unwrap_err(), TacacsError::BodyLengthMismatch { expected: 5 + 0xff08 + 4, actual: 17 });
+    }
+
+    crate::helpers::body_decoding_proptests!(AuthenticationContinue, generate_authentication_continue_data);
+}
@@ -0,0 +1,417 @@
+use des::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use des::Des;
+use md4::Md4;
+use md5::{Digest, Md5};
+use sha1::Sha1;
+
+use crate::error::TacacsError;
+use crate::tacacs_string::TacacsString;
+use crate::constants::{
+    TACACS_CHAP_RESPONSE_LENGTH, TACACS_MSCHAPV2_CHALLENGE_LENGTH, TACACS_MSCHAP_CHALLENGE_LENGTH,
+    TACACS_MSCHAP_NT_RESPONSE_LENGTH, TACACS_MSCHAP_RESPONSE_LENGTH
+};
+
+// RFC 8907 section 5.4.2: the data field of an AuthenticationStart for each of
+// the PPP derived authentication types. The user field always carries the username.
+//
+// PAP:       | password ...
+// CHAP:      | ppp id | challenge ... | response (16) |
+// MS-CHAP:   | ppp id | challenge (8)  | response (49) |
+// MS-CHAPv2: | ppp id | challenge (16) | response (49) |
+
+const MSCHAP_USE_NT_RESPONSE_FLAG: u8 = 0x01;
+const MSCHAP_LM_RESPONSE_LENGTH: usize = 24;
+const MSCHAPV2_PEER_CHALLENGE_LENGTH: usize = 16;
+const MSCHAPV2_RESERVED_LENGTH: usize = 8;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PapCredentials {
+    pub password: TacacsString,
+}
+
+impl PapCredentials {
+    pub fn new(password: &str) -> Self {
+        PapCredentials { password: TacacsString::from(password) }
+    }
+
+    /// Any bytes are a valid PAP password, so unlike the other credentials this cannot fail.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        PapCredentials { password: TacacsString::from_bytes(bytes) }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.password.as_bytes().to_vec()
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        constant_time_eq(self.password.as_bytes(), password.as_bytes())
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChapCredentials {
+    pub ppp_id: u8,
+    pub challenge: Vec<u8>,
+    pub response: [u8; TACACS_CHAP_RESPONSE_LENGTH],
+}
+
+impl ChapCredentials {
+    /// Computes the response locally, for clients that know the password
+    /// rather than relaying a response from a PPP peer.
+    pub fn from_password(ppp_id: u8, challenge: &[u8], password: &str) -> Self {
+        ChapCredentials {
+            ppp_id,
+            challenge: challenge.to_vec(),
+            response: chap_response(ppp_id, challenge, password),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TacacsError> {
+        if bytes.len() < 2 + TACACS_CHAP_RESPONSE_LENGTH {
+            return Err(TacacsError::TooShort { field: "chap data", offset: 0, needed: 2 + TACACS_CHAP_RESPONSE_LENGTH, got: bytes.len() });
+        }
+
+        let (challenge, response) = bytes[1..].split_at(bytes.len() - 1 - TACACS_CHAP_RESPONSE_LENGTH);
+
+        Ok(ChapCredentials {
+            ppp_id: bytes[0],
+            challenge: challenge.to_vec(),
+            response: to_array(response),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.ppp_id];
+        bytes.extend(&self.challenge);
+        bytes.extend(self.response);
+        bytes
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        constant_time_eq(&self.response, &chap_response(self.ppp_id, &self.challenge, password))
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MsChapCredentials {
+    pub ppp_id: u8,
+    pub challenge: [u8; TACACS_MSCHAP_CHALLENGE_LENGTH],
+    pub nt_response: [u8; TACACS_MSCHAP_NT_RESPONSE_LENGTH],
+}
+
+impl MsChapCredentials {
+    pub fn from_password(ppp_id: u8, challenge: [u8; TACACS_MSCHAP_CHALLENGE_LENGTH], password: &str) -> Self {
+        MsChapCredentials {
+            ppp_id,
+            challenge,
+            nt_response: challenge_response(&challenge, &nt_password_hash(password)),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TacacsError> {
+        let expected_length = 1 + TACACS_MSCHAP_CHALLENGE_LENGTH + TACACS_MSCHAP_RESPONSE_LENGTH;
+        if bytes.len() != expected_length {
+            return Err(TacacsError::InvalidLength { field: "ms-chap data", expected: expected_length, actual: bytes.len() });
+        }
+
+        let (challenge, response) = bytes[1..].split_at(TACACS_MSCHAP_CHALLENGE_LENGTH);
+
+        // RFC 2433 section A.5: only the NT response is supported, the LAN Manager response is ignored.
+        let flags = response[TACACS_MSCHAP_RESPONSE_LENGTH - 1];
+        if flags & MSCHAP_USE_NT_RESPONSE_FLAG == 0 {
+            return Err(TacacsError::InvalidValue { field: "ms-chap flags", offset: expected_length - 1, value: flags });
+        }
+
+        let nt_response = &response[MSCHAP_LM_RESPONSE_LENGTH..MSCHAP_LM_RESPONSE_LENGTH + TACACS_MSCHAP_NT_RESPONSE_LENGTH];
+
+        Ok(MsChapCredentials {
+            ppp_id: bytes[0],
+            challenge: to_array(challenge),
+            nt_response: to_array(nt_response),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.ppp_id];
+        bytes.extend(self.challenge);
+        bytes.extend([0u8; MSCHAP_LM_RESPONSE_LENGTH]);
+        bytes.extend(self.nt_response);
+        bytes.push(MSCHAP_USE_NT_RESPONSE_FLAG);
+        bytes
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        constant_time_eq(&self.nt_response, &challenge_response(&self.challenge, &nt_password_hash(password)))
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MsChapV2Credentials {
+    pub ppp_id: u8,
+    pub authenticator_challenge: [u8; TACACS_MSCHAPV2_CHALLENGE_LENGTH],
+    pub peer_challenge: [u8; MSCHAPV2_PEER_CHALLENGE_LENGTH],
+    pub nt_response: [u8; TACACS_MSCHAP_NT_RESPONSE_LENGTH],
+}
+
+impl MsChapV2Credentials {
+    /// The user is the username without any domain prefix, as hashed by the peer.
+    pub fn from_password(ppp_id: u8, authenticator_challenge: [u8; TACACS_MSCHAPV2_CHALLENGE_LENGTH],
+        peer_challenge: [u8; MSCHAPV2_PEER_CHALLENGE_LENGTH], user: &str, password: &str) -> Self
+    {
+        MsChapV2Credentials {
+            ppp_id,
+            authenticator_challenge,
+            peer_challenge,
+            nt_response: mschapv2_nt_response(&authenticator_challenge, &peer_challenge, user, password),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TacacsError> {
+        let expected_length = 1 + TACACS_MSCHAPV2_CHALLENGE_LENGTH + TACACS_MSCHAP_RESPONSE_LENGTH;
+        if bytes.len() != expected_length {
+            return Err(TacacsError::InvalidLength { field: "ms-chapv2 data", expected: expected_length, actual: bytes.len() });
+        }
+
+        let (authenticator_challenge, response) = bytes[1..].split_at(TACACS_MSCHAPV2_CHALLENGE_LENGTH);
+        let (peer_challenge, response) = response.split_at(MSCHAPV2_PEER_CHALLENGE_LENGTH);
+        let nt_response = &response[MSCHAPV2_RESERVED_LENGTH..MSCHAPV2_RESERVED_LENGTH + TACACS_MSCHAP_NT_RESPONSE_LENGTH];
+
+        Ok(MsChapV2Credentials {
+            ppp_id: bytes[0],
+            authenticator_challenge: to_array(authenticator_challenge),
+            peer_challenge: to_array(peer_challenge),
+            nt_response: to_array(nt_response),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.ppp_id];
+        bytes.extend(self.authenticator_challenge);
+        bytes.extend(self.peer_challenge);
+        bytes.extend([0u8; MSCHAPV2_RESERVED_LENGTH]);
+        bytes.extend(self.nt_response);
+        bytes.push(0);
+        bytes
+    }
+
+    pub fn verify(&self, user: &str, password: &str) -> bool {
+        let expected = mschapv2_nt_response(&self.authenticator_challenge, &self.peer_challenge, user, password);
+        constant_time_eq(&self.nt_response, &expected)
+    }
+}
+
+
+// RFC 1994 section 4.1
+fn chap_response(ppp_id: u8, challenge: &[u8], password: &str) -> [u8; TACACS_CHAP_RESPONSE_LENGTH] {
+    let mut hasher = Md5::new();
+    hasher.update([ppp_id]);
+    hasher.update(password.as_bytes());
+    hasher.update(challenge);
+    hasher.finalize().into()
+}
+
+// RFC 2759 section 8.3
+fn nt_password_hash(password: &str) -> [u8; 16] {
+    let unicode_password : Vec<u8> = password.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+
+    let mut hasher = Md4::new();
+    hasher.update(unicode_password);
+    hasher.finalize().into()
+}
+
+// RFC 2759 section 8.2
+fn challenge_hash(peer_challenge: &[u8], authenticator_challenge: &[u8], user: &str) -> [u8; 8] {
+    let mut hasher = Sha1::new();
+    hasher.update(peer_challenge);
+    hasher.update(authenticator_challenge);
+    hasher.update(user.as_bytes());
+
+    let digest = hasher.finalize();
+    let mut hash = [0u8; 8];
+    hash.copy_from_slice(&digest[..8]);
+    hash
+}
+
+// RFC 2759 section 8.1
+fn mschapv2_nt_response(authenticator_challenge: &[u8], peer_challenge: &[u8], user: &str, password: &str) -> [u8; TACACS_MSCHAP_NT_RESPONSE_LENGTH] {
+    let challenge = challenge_hash(peer_challenge, authenticator_challenge, user);
+    challenge_response(&challenge, &nt_password_hash(password))
+}
+
+// RFC 2759 section 8.5: the password hash is zero padded to 21 bytes and split
+// into three 7 byte DES keys, each of which encrypts the challenge.
+fn challenge_response(challenge: &[u8; 8], password_hash: &[u8; 16]) -> [u8; TACACS_MSCHAP_NT_RESPONSE_LENGTH] {
+    let mut padded_hash = [0u8; 21];
+    padded_hash[..16].copy_from_slice(password_hash);
+
+    let mut response = [0u8; TACACS_MSCHAP_NT_RESPONSE_LENGTH];
+    for (key, output) in padded_hash.chunks(7).zip(response.chunks_mut(8)) {
+        let cipher = Des::new(GenericArray::from_slice(&des_key(key)));
+        let mut block = GenericArray::clone_from_slice(challenge);
+        cipher.encrypt_block(&mut block);
+        output.copy_from_slice(&block);
+    }
+
+    response
+}
+
+// Spreads 56 key bits over 8 bytes, leaving the (ignored) parity bit of each byte clear.
+fn des_key(key: &[u8]) -> [u8; 8] {
+    [
+        key[0],
+        (key[0] << 7) | (key[1] >> 1),
+        (key[1] << 6) | (key[2] >> 2),
+        (key[2] << 5) | (key[3] >> 3),
+        (key[3] << 4) | (key[4] >> 4),
+        (key[4] << 3) | (key[5] >> 5),
+        (key[5] << 2) | (key[6] >> 6),
+        key[6] << 1,
+    ]
+}
+
+// Callers have already checked the length of the data being split up.
+fn to_array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0u8; N];
+    array.copy_from_slice(bytes);
+    array
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 2759 section 9.2
+    const USER: &str = "User";
+    const PASSWORD: &str = "clientPass";
+    const AUTHENTICATOR_CHALLENGE: [u8; 16] = [
+        0x5B, 0x5D, 0x7C, 0x7D, 0x7B, 0x3F, 0x2F, 0x3E, 0x3C, 0x2C, 0x60, 0x21, 0x32, 0x26, 0x26, 0x28
+    ];
+    const PEER_CHALLENGE: [u8; 16] = [
+        0x21, 0x40, 0x23, 0x24, 0x25, 0x5E, 0x26, 0x2A, 0x28, 0x29, 0x5F, 0x2B, 0x3A, 0x33, 0x7C, 0x7E
+    ];
+    const NT_RESPONSE: [u8; 24] = [
+        0x82, 0x30, 0x9E, 0xCD, 0x8D, 0x70, 0x8B, 0x5E, 0xA0, 0x8F, 0xAA, 0x39, 0x81, 0xCD, 0x83, 0x54,
+        0x42, 0x33, 0x11, 0x4A, 0x3D, 0x85, 0xD6, 0xDF
+    ];
+
+    #[test]
+    fn test_nt_password_hash() {
+        assert_eq!(nt_password_hash(PASSWORD), [
+            0x44, 0xEB, 0xBA, 0x8D, 0x53, 0x12, 0xB8, 0xD6, 0x11, 0x47, 0x44, 0x11, 0xF5, 0x69, 0x89, 0xAE
+        ]);
+    }
+
+    #[test]
+    fn test_challenge_hash() {
+        assert_eq!(challenge_hash(&PEER_CHALLENGE, &AUTHENTICATOR_CHALLENGE, USER), [
+            0xD0, 0x2E, 0x43, 0x86, 0xBC, 0xE9, 0x12, 0x26
+        ]);
+    }
+
+    #[test]
+    fn test_pap_round_trip() {
+        let credentials = PapCredentials::new("secret");
+        let parsed = PapCredentials::from_bytes(&credentials.to_bytes());
+
+        assert_eq!(parsed, credentials);
+        assert!(parsed.verify("secret"));
+        assert!(!parsed.verify("Secret"));
+    }
+
+    #[test]
+    fn test_chap_round_trip() {
+        let credentials = ChapCredentials::from_password(7, b"0123456789abcdef", "secret");
+        let bytes = credentials.to_bytes();
+        assert_eq!(bytes.len(), 1 + 16 + TACACS_CHAP_RESPONSE_LENGTH);
+
+        let parsed = ChapCredentials::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, credentials);
+        assert!(parsed.verify("secret"));
+        assert!(!parsed.verify("wrong"));
+    }
+
+    #[test]
+    fn test_chap_response() {
+        // MD5(0x07 || "secret" || "challenge")
+        let response = chap_response(7, b"challenge", "secret");
+        let mut hasher = Md5::new();
+        hasher.update(b"\x07secretchallenge");
+        assert_eq!(response, <[u8; 16]>::from(hasher.finalize()));
+    }
+
+    #[test]
+    fn test_chap_from_bytes_too_short() {
+        let result = ChapCredentials::from_bytes(&[0u8; 1 + TACACS_CHAP_RESPONSE_LENGTH]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_mschap_round_trip() {
+        let credentials = MsChapCredentials::from_password(1, [0x10, 0x2D, 0xB5, 0xDF, 0x08, 0x5D, 0x30, 0x41], PASSWORD);
+        let bytes = credentials.to_bytes();
+        assert_eq!(bytes.len(), 1 + TACACS_MSCHAP_CHALLENGE_LENGTH + TACACS_MSCHAP_RESPONSE_LENGTH);
+
+        let parsed = MsChapCredentials::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, credentials);
+        assert!(parsed.verify(PASSWORD));
+        assert!(!parsed.verify("wrong"));
+    }
+
+    #[test]
+    fn test_mschap_lm_only_response() {
+        let mut bytes = MsChapCredentials::from_password(1, [0u8; 8], PASSWORD).to_bytes();
+        *bytes.last_mut().unwrap() = 0;
+
+        let error = MsChapCredentials::from_bytes(&bytes).unwrap_err();
+        assert_eq!(error, TacacsError::InvalidValue { field: "ms-chap flags", offset: 57, value: 0 });
+    }
+
+    #[test]
+    fn test_mschapv2_nt_response() {
+        let credentials = MsChapV2Credentials::from_password(1, AUTHENTICATOR_CHALLENGE, PEER_CHALLENGE, USER, PASSWORD);
+        assert_eq!(credentials.nt_response, NT_RESPONSE);
+    }
+
+    #[test]
+    fn test_mschapv2_round_trip() {
+        let credentials = MsChapV2Credentials {
+            ppp_id: 1,
+            authenticator_challenge: AUTHENTICATOR_CHALLENGE,
+            peer_challenge: PEER_CHALLENGE,
+            nt_response: NT_RESPONSE,
+        };
+        let bytes = credentials.to_bytes();
+        assert_eq!(bytes.len(), 1 + TACACS_MSCHAPV2_CHALLENGE_LENGTH + TACACS_MSCHAP_RESPONSE_LENGTH);
+
+        let parsed = MsChapV2Credentials::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, credentials);
+        assert!(parsed.verify(USER, PASSWORD));
+        assert!(!parsed.verify(USER, "wrong"));
+        assert!(!parsed.verify("Other", PASSWORD));
+    }
+
+    #[test]
+    fn test_mschapv2_from_bytes_invalid_length() {
+        let result = MsChapV2Credentials::from_bytes(&[0u8; 10]);
+        assert!(result.is_err());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_from_bytes_arbitrary_data_does_not_panic(data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..128)) {
+            let _ = PapCredentials::from_bytes(&data);
+            let _ = ChapCredentials::from_bytes(&data);
+            let _ = MsChapCredentials::from_bytes(&data);
+            let _ = MsChapV2Credentials::from_bytes(&data);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod start;
+pub mod reply;
+pub mod continuation;
+pub mod credentials;
\ No newline at end of file
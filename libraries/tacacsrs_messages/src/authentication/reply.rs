@@ -0,0 +1,208 @@
+use std::io::Cursor;
+use crate::tacacs_string::TacacsString;
+use crate::{constants::TACACS_AUTHENTICATION_REPLY_MIN_LENGTH, helpers::{length_u16, read_enum, read_flags, read_string, read_u16}, traits::TacacsBodyTrait};
+use crate::packet::{Packet, PacketTrait};
+use crate::error::TacacsError;
+use crate::enumerations::{TacacsAuthenicationReplyFlags, TacacsAuthenticationStatus};
+
+// 1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8
+// +----------------+----------------+----------------+----------------+
+// |     status     |      flags     |        server_msg_len           |
+// +----------------+----------------+----------------+----------------+
+// |           data_len              |        server_msg ...
+// +----------------+----------------+----------------+----------------+
+// |           data ...
+// +----------------+----------------+
+
+
+#[derive(Debug, Clone)]
+pub struct AuthenticationReply {
+    pub status: TacacsAuthenticationStatus,
+    pub flags: TacacsAuthenicationReplyFlags,
+    pub server_msg: TacacsString,
+    pub data: TacacsString,
+}
+
+impl AuthenticationReply {
+    pub fn from_packet(packet: &Packet) -> Result<Self, TacacsError> {
+        let expected_length = Self::size_from_bytes(packet.body())?;
+        if packet.body().len() < expected_length {
+            return Err(TacacsError::BodyLengthMismatch { expected: expected_length, actual: packet.body().len() });
+        }
+
+        Self::from_bytes(packet.body())
+    }
+
+    fn size_from_bytes(data : &[u8]) -> Result<usize, TacacsError> {
+        let mut cursor = Cursor::new(data);
+        cursor.set_position(2);
+
+        let server_msg_len = read_u16(&mut cursor, "server_msg_len")? as usize;
+        let data_len = read_u16(&mut cursor, "data_len")? as usize;
+
+        Ok(TACACS_AUTHENTICATION_REPLY_MIN_LENGTH + server_msg_len + data_len)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TacacsError> {
+        let mut cursor = Cursor::new(bytes);
+
+        let status = read_enum::<TacacsAuthenticationStatus>(&mut cursor, "status")?;
+        let flags = read_flags::<TacacsAuthenicationReplyFlags>(&mut cursor, "flags")?;
+
+        let server_msg_len = read_u16(&mut cursor, "server_msg_len")? as usize;
+        let data_len = read_u16(&mut cursor, "data_len")? as usize;
+
+        let server_msg = read_string(&mut cursor, server_msg_len, "server_msg")?;
+        let data = read_string(&mut cursor, data_len, "data")?;
+
+        Ok(AuthenticationReply{status, flags, server_msg, data})
+    }
+
+    pub fn is_no_echo(&self) -> bool {
+        self.flags.contains(TacacsAuthenicationReplyFlags::TAC_PLUS_AUTHEN_FLAG_NOECHO)
+    }
+}
+
+
+impl TacacsBodyTrait for AuthenticationReply
+{
+    fn try_to_bytes(&self) -> Result<Vec<u8>, TacacsError> {
+        let mut bytes = vec![
+            self.status as u8,
+            self.flags.bits(),
+        ];
+        bytes.extend(length_u16(self.server_msg.len(), "server_msg")?);
+        bytes.extend(length_u16(self.data.len(), "data")?);
+
+        bytes.extend(self.server_msg.as_bytes());
+        bytes.extend(self.data.as_bytes());
+        Ok(bytes)
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests
+{
+    use crate::{
+        enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType},
+        header::Header
+    };
+
+    use super::*;
+
+    fn generate_authentication_reply_data() -> Vec<u8> {
+        let server_message_string = "Password: ";
+        let data_string = "data";
+
+        let mut data : Vec<u8> = vec![
+            TacacsAuthenticationStatus::TacPlusAuthenStatusGetpass as u8, // 0: status
+            TacacsAuthenicationReplyFlags::TAC_PLUS_AUTHEN_FLAG_NOECHO.bits(), // 1: flags
+        ];
+        data.extend((server_message_string.len() as u16).to_be_bytes()); // 2: server_msg_len
+        data.extend((data_string.len() as u16).to_be_bytes()); // 4: data_len
+
+        data.extend(server_message_string.as_bytes());
+        data.extend(data_string.as_bytes());
+
+        data
+    }
+
+    #[test]
+    fn test_reply_from_bytes() {
+        let bytes = generate_authentication_reply_data();
+        let reply = AuthenticationReply::from_bytes(&bytes).unwrap();
+
+        assert_eq!(reply.status, TacacsAuthenticationStatus::TacPlusAuthenStatusGetpass);
+        assert_eq!(reply.server_msg, "Password: ");
+        assert_eq!(reply.data, "data");
+        assert!(reply.is_no_echo());
+    }
+
+    #[test]
+    fn test_read_bytes_incorrect_status() {
+        let mut data = generate_authentication_reply_data();
+        data[0] = 0xff;
+
+        let error = AuthenticationReply::from_bytes(&data).unwrap_err();
+        assert_eq!(error, TacacsError::InvalidValue { field: "status", offset: 0, value: 0xff });
+    }
+
+    #[test]
+    fn test_read_bytes_incorrect_flags() {
+        let mut data = generate_authentication_reply_data();
+        data[1] = 0xff;
+
+        let error = AuthenticationReply::from_bytes(&data).unwrap_err();
+        assert_eq!(error, TacacsError::InvalidValue { field: "flags", offset: 1, value: 0xff });
+    }
+
+    #[test]
+    fn test_read_bytes_truncated() {
+        let data = generate_authentication_reply_data();
+
+        let error = AuthenticationReply::from_bytes(&data[..data.len()-1]).unwrap_err();
+        assert_eq!(error, TacacsError::TooShort { field: "data", offset: 16, needed: 20, got: 19 });
+    }
+
+    #[test]
+    fn test_reply_to_bytes() {
+        let bytes = generate_authentication_reply_data();
+        let reply = AuthenticationReply::from_bytes(&bytes).unwrap();
+
+        assert_eq!(reply.try_to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_reply_size_from_bytes() {
+        let bytes = generate_authentication_reply_data();
+        let size = AuthenticationReply::size_from_bytes(&bytes).unwrap();
+
+        assert_eq!(size, bytes.len());
+    }
+
+    #[test]
+    fn test_reply_from_packet() {
+        let data = generate_authentication_reply_data();
+        let header = Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAuthentication,
+            seq_no: 2,
+            flags: TacacsFlags::empty(),
+            session_id: 0,
+            length: data.len() as u32,
+        };
+
+        let packet = Packet::new(header, data).unwrap();
+        let reply = AuthenticationReply::from_packet(&packet).unwrap();
+
+        assert_eq!(reply.status, TacacsAuthenticationStatus::TacPlusAuthenStatusGetpass);
+        assert_eq!(reply.server_msg, "Password: ");
+        assert_eq!(reply.data, "data");
+    }
+
+    #[test]
+    fn test_reply_from_packet_invalid_length() {
+        let mut data = generate_authentication_reply_data();
+        data[2] = 0xff; // first byte of server_msg_len is set to 0xff
+
+        let header = Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAuthentication,
+            seq_no: 2,
+            flags: TacacsFlags::empty(),
+            session_id: 0,
+            length: data.len() as u32,
+        };
+
+        let packet = Packet::new(header, data).unwrap();
+        let reply = AuthenticationReply::from_packet(&packet);
+
+        assert!(reply.is_err());
+        assert_eq!(reply.unwrap_err(), TacacsError::BodyLengthMismatch { expected: 6 + 0xff0a + 4, actual: 20 });
+    }
+
+    crate::helpers::body_decoding_proptests!(AuthenticationReply, generate_authentication_reply_data);
+}
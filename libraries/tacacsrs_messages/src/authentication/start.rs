@@ -0,0 +1,280 @@
+use crate::enumerations::{TacacsAuthenticationAction, TacacsAuthenticationService, TacacsAuthenticationType, TacacsMinorVersion};
+use crate::tacacs_string::TacacsString;
+use crate::error::TacacsError;
+use crate::helpers::{length_u8, read_open_enum, read_string, read_u8};
+use crate::packet::{Packet, PacketTrait};
+use crate::traits::TacacsBodyTrait;
+use std::io::Cursor;
+use crate::constants::TACACS_AUTHENTICATION_START_MIN_LENGTH;
+
+// 1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8
+// +----------------+----------------+----------------+----------------+
+// |    action      |    priv_lvl    |  authen_type   | authen_service |
+// +----------------+----------------+----------------+----------------+
+// |    user_len    |    port_len    |  rem_addr_len  |    data_len    |
+// +----------------+----------------+----------------+----------------+
+// |    user ...
+// +----------------+----------------+----------------+----------------+
+// |    port ...
+// +----------------+----------------+----------------+----------------+
+// |    rem_addr ...
+// +----------------+----------------+----------------+----------------+
+// |    data...
+// +----------------+----------------+----------------+----------------+
+
+#[derive(Debug, Clone)]
+pub struct AuthenticationStart {
+    pub action: TacacsAuthenticationAction,
+    pub priv_lvl: u8,
+    pub authen_type: TacacsAuthenticationType,
+    pub authen_service: TacacsAuthenticationService,
+    pub user: TacacsString,
+    pub port: TacacsString,
+    pub rem_address: TacacsString,
+    pub data: TacacsString
+}
+
+impl AuthenticationStart {
+    /// RFC 8907 section 5.4.1: ASCII logins use the default minor version, while the
+    /// PAP, CHAP and MS-CHAP variants use minor version one.
+    pub fn minor_version(&self) -> TacacsMinorVersion {
+        match self.authen_type {
+            TacacsAuthenticationType::TacPlusAuthenTypePap |
+            TacacsAuthenticationType::TacPlusAuthenTypeChap |
+            TacacsAuthenticationType::TacPlusAuthenTypeMschap |
+            TacacsAuthenticationType::TacPlusAuthenTypeMschapv2 => TacacsMinorVersion::TacacsPlusMinorVerOne,
+            _ => TacacsMinorVersion::TacacsPlusMinorVerDefault,
+        }
+    }
+
+    pub fn from_packet(packet : &Packet) -> Result<Self, TacacsError> {
+        // Check if the packet the correct length
+        let expected_length = Self::size_from_bytes(packet.body())?;
+        if packet.body().len() < expected_length {
+            return Err(TacacsError::BodyLengthMismatch { expected: expected_length, actual: packet.body().len() });
+        }
+
+        Self::from_bytes(packet.body())
+    }
+
+    fn size_from_bytes(data : &[u8]) -> Result<usize, TacacsError> {
+        if data.len() < TACACS_AUTHENTICATION_START_MIN_LENGTH {
+            return Err(TacacsError::TooShort { field: "header", offset: 0, needed: TACACS_AUTHENTICATION_START_MIN_LENGTH, got: data.len() });
+        }
+
+        let user_len = data[4] as usize;
+        let port_len = data[5] as usize;
+        let rem_addr_len = data[6] as usize;
+        let data_len = data[7] as usize;
+
+        Ok(TACACS_AUTHENTICATION_START_MIN_LENGTH + user_len + port_len + rem_addr_len + data_len)
+    }
+
+    pub fn from_bytes(data : &[u8]) -> Result<Self, TacacsError> {
+        if data.len() < TACACS_AUTHENTICATION_START_MIN_LENGTH {
+            return Err(TacacsError::TooShort { field: "header", offset: 0, needed: TACACS_AUTHENTICATION_START_MIN_LENGTH, got: data.len() });
+        }
+
+        let mut cursor = Cursor::new(data);
+
+        let action = read_open_enum::<TacacsAuthenticationAction>(&mut cursor, "action")?;
+        let priv_lvl = read_u8(&mut cursor, "priv_lvl")?;
+        let authen_type = read_open_enum::<TacacsAuthenticationType>(&mut cursor, "authen_type")?;
+        let authen_service = read_open_enum::<TacacsAuthenticationService>(&mut cursor, "authen_service")?;
+
+        let user_len = read_u8(&mut cursor, "user_len")?;
+        let port_len = read_u8(&mut cursor, "port_len")?;
+        let rem_addr_len = read_u8(&mut cursor, "rem_addr_len")?;
+        let data_len = read_u8(&mut cursor, "data_len")?;
+
+        let user = read_string(&mut cursor, user_len as usize, "user")?;
+        let port = read_string(&mut cursor, port_len as usize, "port")?;
+        let rem_address = read_string(&mut cursor, rem_addr_len as usize, "rem_address")?;
+        let data = read_string(&mut cursor, data_len as usize, "data")?;
+
+        Ok(AuthenticationStart {
+            action,
+            priv_lvl,
+            authen_type,
+            authen_service,
+            user,
+            port,
+            rem_address,
+            data
+        })
+    }
+}
+
+
+impl TacacsBodyTrait for AuthenticationStart
+{
+    fn try_to_bytes(&self) -> Result<Vec<u8>, TacacsError> {
+        let mut data = vec![
+            u8::from(self.action),
+            self.priv_lvl,
+            u8::from(self.authen_type),
+            u8::from(self.authen_service),
+            length_u8(self.user.len(), "user")?,
+            length_u8(self.port.len(), "port")?,
+            length_u8(self.rem_address.len(), "rem_address")?,
+            length_u8(self.data.len(), "data")?,
+        ];
+
+        data.extend(self.user.as_bytes());
+        data.extend(self.port.as_bytes());
+        data.extend(self.rem_address.as_bytes());
+        data.extend(self.data.as_bytes());
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType};
+    use crate::header::Header;
+
+    use super::*;
+
+    fn generate_authentication_start_data() -> Vec<u8>
+    {
+        let mut data : Vec<u8> = vec![
+            u8::from(TacacsAuthenticationAction::TacPlusAuthenLogin), // 0: action
+            1, // 1: priv_lvl
+            u8::from(TacacsAuthenticationType::TacPlusAuthenTypePap), // 2: authen_type
+            u8::from(TacacsAuthenticationService::TacPlusAuthenSvcLogin), // 3: authen_service
+            5, // 4: user_len
+            4, // 5: port_len
+            7, // 6: rem_addr_len
+            8, // 7: data_len
+        ];
+
+        data.extend(b"admin"); // 8: user
+        data.extend(b"tty0"); // 13: port
+        data.extend(b"1.1.1.1"); // 17: rem_addr
+        data.extend(b"password"); // 24: data
+
+        data
+    }
+
+    fn generate_header(length : usize) -> Header
+    {
+        Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerOne,
+            tacacs_type: TacacsType::TacPlusAuthentication,
+            seq_no: 1,
+            flags: TacacsFlags::empty(),
+            session_id: 0,
+            length: length as u32,
+        }
+    }
+
+    #[test]
+    fn test_size_from_bytes() {
+        let data = generate_authentication_start_data();
+        let size = AuthenticationStart::size_from_bytes(&data).unwrap();
+        assert_eq!(size, data.len());
+    }
+
+    #[test]
+    fn test_size_from_bytes_too_short() {
+        let data = generate_authentication_start_data();
+        let result = AuthenticationStart::size_from_bytes(&data[..TACACS_AUTHENTICATION_START_MIN_LENGTH - 1]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_data() {
+        let data = generate_authentication_start_data();
+        let authentication_start = AuthenticationStart::from_bytes(data.as_slice()).unwrap();
+
+        assert_eq!(authentication_start.action, TacacsAuthenticationAction::TacPlusAuthenLogin);
+        assert_eq!(authentication_start.priv_lvl, 1);
+        assert_eq!(authentication_start.authen_type, TacacsAuthenticationType::TacPlusAuthenTypePap);
+        assert_eq!(authentication_start.authen_service, TacacsAuthenticationService::TacPlusAuthenSvcLogin);
+        assert_eq!(authentication_start.user, "admin");
+        assert_eq!(authentication_start.port, "tty0");
+        assert_eq!(authentication_start.rem_address, "1.1.1.1");
+        assert_eq!(authentication_start.data, "password");
+    }
+
+    #[test]
+    fn test_to_data() {
+        let data = generate_authentication_start_data();
+        let authentication_start = AuthenticationStart::from_bytes(data.as_slice()).unwrap();
+
+        assert_eq!(authentication_start.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_bytes_not_enough_data() {
+        let data = generate_authentication_start_data();
+        let result = AuthenticationStart::from_bytes(&data[..TACACS_AUTHENTICATION_START_MIN_LENGTH - 1]);
+
+        let err = result.unwrap_err();
+        assert_eq!(err, TacacsError::TooShort { field: "header", offset: 0, needed: TACACS_AUTHENTICATION_START_MIN_LENGTH, got: TACACS_AUTHENTICATION_START_MIN_LENGTH - 1 });
+    }
+
+    #[test]
+    fn test_read_bytes_unknown_action() {
+        let mut data = generate_authentication_start_data();
+        data[0] = 0xff;
+
+        let request = AuthenticationStart::from_bytes(data.as_slice()).unwrap();
+        assert_eq!(request.action, TacacsAuthenticationAction::Unknown(0xff));
+        assert_eq!(request.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_bytes_unknown_authen_type() {
+        let mut data = generate_authentication_start_data();
+        data[2] = 0xff;
+
+        let request = AuthenticationStart::from_bytes(data.as_slice()).unwrap();
+        assert_eq!(request.authen_type, TacacsAuthenticationType::Unknown(0xff));
+        assert_eq!(request.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_bytes_unknown_authen_service() {
+        let mut data = generate_authentication_start_data();
+        data[3] = 0xff;
+
+        let request = AuthenticationStart::from_bytes(data.as_slice()).unwrap();
+        assert_eq!(request.authen_service, TacacsAuthenticationService::Unknown(0xff));
+        assert_eq!(request.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_bytes_truncated_data() {
+        let data = generate_authentication_start_data();
+
+        let err = AuthenticationStart::from_bytes(&data[..data.len() - 1]).unwrap_err();
+        assert_eq!(err, TacacsError::TooShort { field: "data", offset: 24, needed: 32, got: 31 });
+    }
+
+    #[test]
+    fn test_from_packet() {
+        let data = generate_authentication_start_data();
+        let packet = Packet::new(generate_header(data.len()), data).unwrap();
+
+        let authentication_start = AuthenticationStart::from_packet(&packet).unwrap();
+
+        assert_eq!(authentication_start.try_to_bytes().unwrap(), packet.body().clone());
+    }
+
+    #[test]
+    fn test_correct_packet_size_with_invalid_size_based_on_parameters() {
+        let mut data = generate_authentication_start_data();
+        data[4] = 255; // Set user_len to 255
+
+        let packet = Packet::new(generate_header(data.len()), data).unwrap();
+
+        let err = AuthenticationStart::from_packet(&packet).unwrap_err();
+        assert_eq!(err, TacacsError::BodyLengthMismatch { expected: 282, actual: 32 });
+    }
+
+    crate::helpers::body_decoding_proptests!(AuthenticationStart, generate_authentication_start_data);
+}
@@ -0,0 +1,256 @@
+use std::io::Cursor;
+use crate::arguments::argument::ArgumentList;
+use crate::tacacs_string::TacacsString;
+use crate::{constants::{TACACS_AUTHORIZATION_REPLY_MIN_LENGTH, TACACS_AUTHORIZATION_REPLY_ARG_SIZE_OFFSET}, helpers::{length_u16, length_u8, lengths_at, read_enum, read_argument, read_string, read_u16, read_u8}, traits::TacacsBodyTrait};
+use crate::packet::{Packet, PacketTrait};
+use crate::error::TacacsError;
+use crate::enumerations::TacacsAuthorizationStatus;
+
+// 1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8
+// +----------------+----------------+----------------+----------------+
+// |    status      |     arg_cnt    |         server_msg len          |
+// +----------------+----------------+----------------+----------------+
+// +            data_len             |    arg_1_len   |    arg_2_len   |
+// +----------------+----------------+----------------+----------------+
+// |      ...       |   arg_N_len    |         server_msg ...
+// +----------------+----------------+----------------+----------------+
+// |         data ...
+// +----------------+----------------+----------------+----------------+
+// |         arg_1 ...
+// +----------------+----------------+----------------+----------------+
+// |         arg_2 ...
+// +----------------+----------------+----------------+----------------+
+// |         ...
+// +----------------+----------------+----------------+----------------+
+// |         arg_N ...
+// +----------------+----------------+----------------+----------------+
+
+
+#[derive(Debug, Clone)]
+pub struct AuthorizationReply {
+    pub status: TacacsAuthorizationStatus,
+    pub args: ArgumentList,
+    pub server_msg: TacacsString,
+    pub data: TacacsString,
+}
+
+impl AuthorizationReply {
+    pub fn from_packet(packet: &Packet) -> Result<Self, TacacsError> {
+        let expected_length = Self::size_from_bytes(packet.body())?;
+        if packet.body().len() < expected_length {
+            return Err(TacacsError::BodyLengthMismatch { expected: expected_length, actual: packet.body().len() });
+        }
+
+        Self::from_bytes(packet.body())
+    }
+
+    fn size_from_bytes(data : &[u8]) -> Result<usize, TacacsError> {
+        let mut cursor = Cursor::new(data);
+        cursor.set_position(1);
+
+        let arg_cnt = read_u8(&mut cursor, "arg_cnt")? as usize;
+        let server_msg_len = read_u16(&mut cursor, "server_msg_len")? as usize;
+        let data_len = read_u16(&mut cursor, "data_len")? as usize;
+
+        let arg_sizes = lengths_at(data, TACACS_AUTHORIZATION_REPLY_ARG_SIZE_OFFSET, arg_cnt, "arg_size")?;
+
+        let args_len = arg_sizes.iter().map(|size| *size as usize).sum::<usize>();
+
+        Ok(TACACS_AUTHORIZATION_REPLY_MIN_LENGTH + arg_cnt + server_msg_len + data_len + args_len)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TacacsError> {
+        let mut cursor = Cursor::new(bytes);
+
+        let status = read_enum::<TacacsAuthorizationStatus>(&mut cursor, "status")?;
+
+        let arg_cnt = read_u8(&mut cursor, "arg_cnt")?;
+        let server_msg_len = read_u16(&mut cursor, "server_msg_len")? as usize;
+        let data_len = read_u16(&mut cursor, "data_len")? as usize;
+
+        let mut arg_sizes : Vec<u8> = Vec::with_capacity(arg_cnt as usize);
+        for _ in 0..arg_cnt {
+            arg_sizes.push(read_u8(&mut cursor, "arg_size")?);
+        }
+
+        let server_msg = read_string(&mut cursor, server_msg_len, "server_msg")?;
+        let data = read_string(&mut cursor, data_len, "data")?;
+
+        let mut args = ArgumentList::new();
+        for arg_size in arg_sizes {
+            args.push(read_argument(&mut cursor, arg_size as usize, "arg")?);
+        }
+
+        Ok(AuthorizationReply{status, args, server_msg, data})
+    }
+}
+
+
+impl TacacsBodyTrait for AuthorizationReply
+{
+    fn try_to_bytes(&self) -> Result<Vec<u8>, TacacsError> {
+        let mut bytes = vec![
+            self.status as u8,
+            length_u8(self.args.len(), "args")?,
+        ];
+        bytes.extend(length_u16(self.server_msg.len(), "server_msg")?);
+        bytes.extend(length_u16(self.data.len(), "data")?);
+
+        for arg in &self.args {
+            bytes.push(length_u8(arg.encoded_len(), "arg")?);
+        }
+
+        bytes.extend(self.server_msg.as_bytes());
+        bytes.extend(self.data.as_bytes());
+        for arg in &self.args {
+            bytes.extend(arg.to_bytes());
+        }
+
+        Ok(bytes)
+    }
+}
+
+
+#[cfg(test)]
+pub mod tests
+{
+    use crate::{
+        enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType},
+        header::Header
+    };
+
+    use crate::arguments::argument::Argument;
+    use super::*;
+
+    fn generate_authorization_reply_data() -> Vec<u8> {
+        let server_message_string = "server_msg";
+        let data_string = "data";
+        let args = ["priv-lvl=15", "timeout=60"];
+
+        let mut data : Vec<u8> = vec![
+            TacacsAuthorizationStatus::TacPlusPassAdd as u8, // 0: status
+            args.len() as u8, // 1: arg_cnt
+        ];
+        data.extend((server_message_string.len() as u16).to_be_bytes()); // 2: server_msg_len
+        data.extend((data_string.len() as u16).to_be_bytes()); // 4: data_len
+        for arg in args {
+            data.push(arg.len() as u8); // 6+n: arg_n_len
+        }
+
+        data.extend(server_message_string.as_bytes());
+        data.extend(data_string.as_bytes());
+        for arg in args {
+            data.extend(arg.as_bytes());
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_reply_from_bytes() {
+        let bytes = generate_authorization_reply_data();
+        let reply = AuthorizationReply::from_bytes(&bytes).unwrap();
+
+        assert_eq!(reply.status, TacacsAuthorizationStatus::TacPlusPassAdd);
+        assert_eq!(reply.server_msg, "server_msg");
+        assert_eq!(reply.data, "data");
+        assert_eq!(reply.args, vec![Argument::mandatory("priv-lvl", "15"), Argument::mandatory("timeout", "60")].into());
+        assert_eq!(reply.args.get_numeric("timeout").unwrap(), Some(60));
+    }
+
+    #[test]
+    fn test_malformed_arg_is_kept() {
+        let mut data = generate_authorization_reply_data();
+        data[30] = b':'; // arg_1: priv-lvl:15
+
+        let reply = AuthorizationReply::from_bytes(&data).unwrap();
+        assert!(!reply.args[0].is_well_formed());
+        assert_eq!(reply.args.get_numeric("timeout").unwrap(), Some(60));
+        assert_eq!(reply.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_bytes_incorrect_status() {
+        let mut data = generate_authorization_reply_data();
+        data[0] = 0xff;
+
+        let error = AuthorizationReply::from_bytes(&data).unwrap_err();
+        assert_eq!(error, TacacsError::InvalidValue { field: "status", offset: 0, value: 0xff });
+    }
+
+    #[test]
+    fn test_read_bytes_truncated() {
+        let data = generate_authorization_reply_data();
+
+        let error = AuthorizationReply::from_bytes(&data[..data.len()-1]).unwrap_err();
+        assert_eq!(error, TacacsError::TooShort { field: "arg", offset: 33, needed: 43, got: 42 });
+    }
+
+    #[test]
+    fn test_reply_to_bytes() {
+        let bytes = generate_authorization_reply_data();
+        let reply = AuthorizationReply::from_bytes(&bytes).unwrap();
+
+        assert_eq!(reply.try_to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_reply_size_from_bytes() {
+        let bytes = generate_authorization_reply_data();
+        let size = AuthorizationReply::size_from_bytes(&bytes).unwrap();
+
+        assert_eq!(size, bytes.len());
+    }
+
+    #[test]
+    fn test_reply_size_from_bytes_missing_arg_sizes() {
+        let bytes = generate_authorization_reply_data();
+        let result = AuthorizationReply::size_from_bytes(&bytes[..TACACS_AUTHORIZATION_REPLY_MIN_LENGTH + 1]);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_reply_from_packet() {
+        let data = generate_authorization_reply_data();
+        let header = Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAuthorisation,
+            seq_no: 2,
+            flags: TacacsFlags::empty(),
+            session_id: 0,
+            length: data.len() as u32,
+        };
+
+        let packet = Packet::new(header, data).unwrap();
+        let reply = AuthorizationReply::from_packet(&packet).unwrap();
+
+        assert_eq!(reply.status, TacacsAuthorizationStatus::TacPlusPassAdd);
+        assert_eq!(reply.args.len(), 2);
+    }
+
+    #[test]
+    fn test_reply_from_packet_invalid_length() {
+        let mut data = generate_authorization_reply_data();
+        data[2] = 0xff; // first byte of server_msg_len is set to 0xff
+
+        let header = Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAuthorisation,
+            seq_no: 2,
+            flags: TacacsFlags::empty(),
+            session_id: 0,
+            length: data.len() as u32,
+        };
+
+        let packet = Packet::new(header, data).unwrap();
+        let reply = AuthorizationReply::from_packet(&packet);
+
+        assert!(reply.is_err());
+        assert_eq!(reply.unwrap_err(), TacacsError::BodyLengthMismatch { expected: 8 + 0xff0a + 4 + 21, actual: 43 });
+    }
+
+    crate::helpers::body_decoding_proptests!(AuthorizationReply, generate_authorization_reply_data);
+}
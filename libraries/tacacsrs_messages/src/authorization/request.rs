@@ -0,0 +1,304 @@
+use crate::enumerations::{TacacsAuthenticationMethod, TacacsAuthenticationService, TacacsAuthenticationType};
+use crate::arguments::argument::ArgumentList;
+use crate::tacacs_string::TacacsString;
+use crate::error::TacacsError;
+use crate::helpers::{length_u8, lengths_at, read_open_enum, read_argument, read_string, read_u8};
+use crate::packet::{Packet, PacketTrait};
+use crate::traits::TacacsBodyTrait;
+use std::io::Cursor;
+use crate::constants::{TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH, TACACS_AUTHORIZATION_REQUEST_ARG_SIZE_OFFSET};
+
+// 1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8  1 2 3 4 5 6 7 8
+// +----------------+----------------+----------------+----------------+
+// |  authen_method |    priv_lvl    |  authen_type   | authen_service |
+// +----------------+----------------+----------------+----------------+
+// |    user_len    |    port_len    |  rem_addr_len  |    arg_cnt     |
+// +----------------+----------------+----------------+----------------+
+// |   arg_1_len    |   arg_2_len    |      ...       |   arg_N_len    |
+// +----------------+----------------+----------------+----------------+
+// |   user ...
+// +----------------+----------------+----------------+----------------+
+// |   port ...
+// +----------------+----------------+----------------+----------------+
+// |   rem_addr ...
+// +----------------+----------------+----------------+----------------+
+// |   arg_1 ...
+// +----------------+----------------+----------------+----------------+
+// |   arg_2 ...
+// +----------------+----------------+----------------+----------------+
+// |   ...
+// +----------------+----------------+----------------+----------------+
+// |   arg_N ...
+// +----------------+----------------+----------------+----------------+
+
+#[derive(Debug, Clone)]
+pub struct AuthorizationRequest {
+    pub authen_method: TacacsAuthenticationMethod,
+    pub priv_lvl: u8,
+    pub authen_type: TacacsAuthenticationType,
+    pub authen_service: TacacsAuthenticationService,
+    pub user: TacacsString,
+    pub port: TacacsString,
+    pub rem_address: TacacsString,
+    pub args: ArgumentList
+}
+
+impl AuthorizationRequest {
+    pub fn from_packet(packet : &Packet) -> Result<Self, TacacsError> {
+        // Check if the packet the correct length
+        let expected_length = Self::size_from_bytes(packet.body())?;
+        if packet.body().len() < expected_length {
+            return Err(TacacsError::BodyLengthMismatch { expected: expected_length, actual: packet.body().len() });
+        }
+
+        Self::from_bytes(packet.body())
+    }
+
+    fn size_from_bytes(data : &[u8]) -> Result<usize, TacacsError> {
+        if data.len() < TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH {
+            return Err(TacacsError::TooShort { field: "header", offset: 0, needed: TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH, got: data.len() });
+        }
+
+        let mut length = TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH;
+
+        length += data[4] as usize; // user_len
+        length += data[5] as usize; // port_len
+        length += data[6] as usize; // rem_addr_len
+
+        // The sizes of the arguments are stored as an array
+        // in the data directly after the fixed fields.
+        let arg_cnt = data[7] as usize;
+        let arg_sizes = lengths_at(data, TACACS_AUTHORIZATION_REQUEST_ARG_SIZE_OFFSET, arg_cnt, "arg_size")?;
+
+        length += arg_cnt;
+        length += arg_sizes.iter().map(|size| *size as usize).sum::<usize>();
+
+        Ok(length)
+    }
+
+    pub fn from_bytes(data : &[u8]) -> Result<Self, TacacsError> {
+        if data.len() < TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH {
+            return Err(TacacsError::TooShort { field: "header", offset: 0, needed: TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH, got: data.len() });
+        }
+
+        let mut cursor = Cursor::new(data);
+
+        let authen_method = read_open_enum::<TacacsAuthenticationMethod>(&mut cursor, "authen_method")?;
+        let priv_lvl = read_u8(&mut cursor, "priv_lvl")?;
+        let authen_type = read_open_enum::<TacacsAuthenticationType>(&mut cursor, "authen_type")?;
+        let authen_service = read_open_enum::<TacacsAuthenticationService>(&mut cursor, "authen_service")?;
+
+        let user_len = read_u8(&mut cursor, "user_len")?;
+        let port_len = read_u8(&mut cursor, "port_len")?;
+        let rem_addr_len = read_u8(&mut cursor, "rem_addr_len")?;
+        let arg_cnt = read_u8(&mut cursor, "arg_cnt")?;
+
+        let mut arg_sizes : Vec<u8> = Vec::with_capacity(arg_cnt as usize);
+        for _ in 0..arg_cnt {
+            arg_sizes.push(read_u8(&mut cursor, "arg_size")?);
+        }
+
+        let user = read_string(&mut cursor, user_len as usize, "user")?;
+        let port = read_string(&mut cursor, port_len as usize, "port")?;
+        let rem_address = read_string(&mut cursor, rem_addr_len as usize, "rem_address")?;
+
+        let mut args = ArgumentList::new();
+        for arg_size in arg_sizes {
+            args.push(read_argument(&mut cursor, arg_size as usize, "arg")?);
+        }
+
+        Ok(AuthorizationRequest {
+            authen_method,
+            priv_lvl,
+            authen_type,
+            authen_service,
+            user,
+            port,
+            rem_address,
+            args
+        })
+    }
+}
+
+
+impl TacacsBodyTrait for AuthorizationRequest
+{
+    fn try_to_bytes(&self) -> Result<Vec<u8>, TacacsError> {
+        let mut data = vec![
+            u8::from(self.authen_method),
+            self.priv_lvl,
+            u8::from(self.authen_type),
+            u8::from(self.authen_service),
+            length_u8(self.user.len(), "user")?,
+            length_u8(self.port.len(), "port")?,
+            length_u8(self.rem_address.len(), "rem_address")?,
+            length_u8(self.args.len(), "args")?,
+        ];
+
+        for arg in &self.args {
+            data.push(length_u8(arg.encoded_len(), "arg")?);
+        }
+
+        data.extend(self.user.as_bytes());
+        data.extend(self.port.as_bytes());
+        data.extend(self.rem_address.as_bytes());
+        for arg in &self.args {
+            data.extend(arg.to_bytes());
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType};
+    use crate::header::Header;
+
+    use crate::arguments::argument::Argument;
+    use super::*;
+
+    fn generate_authorization_request_data() -> Vec<u8>
+    {
+        let mut data : Vec<u8> = vec![
+            u8::from(TacacsAuthenticationMethod::TacPlusAuthenMethodTacacsplus), // 0: authen_method
+            15, // 1: priv_lvl
+            u8::from(TacacsAuthenticationType::TacPlusAuthenTypeAscii), // 2: authen_type
+            u8::from(TacacsAuthenticationService::TacPlusAuthenSvcLogin), // 3: authen_service
+            1, // 4: user_len
+            1, // 5: port_len
+            1, // 6: rem_addr_len
+            2, // 7: arg_cnt
+            13, // 8+0: arg_1_len
+            8, // 8+1: arg_2_len
+        ];
+
+        data.push(b'A'); // 10: user
+        data.push(b'B'); // 11: port
+        data.push(b'C'); // 12: rem_addr
+        data.extend(b"service=shell"); // 13: arg_1
+        data.extend(b"cmd=show"); // 26: arg_2
+
+        data
+    }
+
+    fn generate_header(length : usize) -> Header
+    {
+        Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAuthorisation,
+            seq_no: 1,
+            flags: TacacsFlags::empty(),
+            session_id: 0,
+            length: length as u32,
+        }
+    }
+
+    #[test]
+    fn test_size_from_bytes() {
+        let data = generate_authorization_request_data();
+        let size = AuthorizationRequest::size_from_bytes(&data).unwrap();
+        assert_eq!(size, data.len());
+    }
+
+    #[test]
+    fn test_size_from_bytes_missing_arg_sizes() {
+        let data = generate_authorization_request_data();
+        let result = AuthorizationRequest::size_from_bytes(&data[..TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH + 1]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_data() {
+        let data = generate_authorization_request_data();
+        let authorization_request = AuthorizationRequest::from_bytes(data.as_slice()).unwrap();
+
+        assert_eq!(authorization_request.authen_method, TacacsAuthenticationMethod::TacPlusAuthenMethodTacacsplus);
+        assert_eq!(authorization_request.priv_lvl, 15);
+        assert_eq!(authorization_request.authen_type, TacacsAuthenticationType::TacPlusAuthenTypeAscii);
+        assert_eq!(authorization_request.authen_service, TacacsAuthenticationService::TacPlusAuthenSvcLogin);
+        assert_eq!(authorization_request.user, "A");
+        assert_eq!(authorization_request.port, "B");
+        assert_eq!(authorization_request.rem_address, "C");
+        assert_eq!(authorization_request.args, vec![Argument::mandatory("service", "shell"), Argument::mandatory("cmd", "show")].into());
+    }
+
+    #[test]
+    fn test_to_data() {
+        let data = generate_authorization_request_data();
+        let authorization_request = AuthorizationRequest::from_bytes(data.as_slice()).unwrap();
+
+        assert_eq!(authorization_request.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_bytes_not_enough_data() {
+        let data = generate_authorization_request_data();
+        let err = AuthorizationRequest::from_bytes(&data[..TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH - 1]).unwrap_err();
+
+        assert_eq!(err, TacacsError::TooShort { field: "header", offset: 0, needed: TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH, got: TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH - 1 });
+    }
+
+    #[test]
+    fn test_read_bytes_unknown_authen_method() {
+        let mut data = generate_authorization_request_data();
+        data[0] = 0xff;
+
+        let request = AuthorizationRequest::from_bytes(data.as_slice()).unwrap();
+        assert_eq!(request.authen_method, TacacsAuthenticationMethod::Unknown(0xff));
+        assert_eq!(request.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_bytes_unknown_authen_type() {
+        let mut data = generate_authorization_request_data();
+        data[2] = 0xff;
+
+        let request = AuthorizationRequest::from_bytes(data.as_slice()).unwrap();
+        assert_eq!(request.authen_type, TacacsAuthenticationType::Unknown(0xff));
+        assert_eq!(request.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_read_bytes_unknown_authen_service() {
+        let mut data = generate_authorization_request_data();
+        data[3] = 0xff;
+
+        let request = AuthorizationRequest::from_bytes(data.as_slice()).unwrap();
+        assert_eq!(request.authen_service, TacacsAuthenticationService::Unknown(0xff));
+        assert_eq!(request.try_to_bytes().unwrap(), data);
+    }
+
+    #[test]
+    fn test_packet_has_nonzero_argcount_but_missing_arg_sizes_data() {
+        let data = generate_authorization_request_data();
+        let err = AuthorizationRequest::from_bytes(&data[..TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH]).unwrap_err();
+
+        assert_eq!(err, TacacsError::TooShort { field: "arg_size", offset: 8, needed: 9, got: 8 });
+    }
+
+    #[test]
+    fn test_from_packet() {
+        let data = generate_authorization_request_data();
+        let packet = Packet::new(generate_header(data.len()), data).unwrap();
+
+        let authorization_request = AuthorizationRequest::from_packet(&packet).unwrap();
+
+        assert_eq!(authorization_request.try_to_bytes().unwrap(), packet.body().clone());
+    }
+
+    #[test]
+    fn test_correct_packet_size_with_invalid_size_based_on_parameters() {
+        let mut data = generate_authorization_request_data();
+        data[4] = 255; // Set user_len to 255
+
+        let packet = Packet::new(generate_header(data.len()), data).unwrap();
+
+        let err = AuthorizationRequest::from_packet(&packet).unwrap_err();
+        assert_eq!(err, TacacsError::BodyLengthMismatch { expected: 288, actual: 34 });
+    }
+
+    crate::helpers::body_decoding_proptests!(AuthorizationRequest, generate_authorization_request_data);
+}
@@ -0,0 +1,250 @@
+use crate::accounting::{reply::AccountingReply, request::AccountingRequest};
+use crate::authentication::{continuation::AuthenticationContinue, reply::AuthenticationReply, start::AuthenticationStart};
+use crate::authorization::{reply::AuthorizationReply, request::AuthorizationRequest};
+use crate::enumerations::{TacacsFlags, TacacsMinorVersion, TacacsType};
+use crate::error::TacacsError;
+use crate::packet::{Packet, PacketTrait};
+use crate::traits::TacacsBodyTrait;
+
+/// Any decoded packet body.
+#[derive(Debug, Clone)]
+pub enum TacacsBody {
+    AuthenticationStart(AuthenticationStart),
+    AuthenticationReply(AuthenticationReply),
+    AuthenticationContinue(AuthenticationContinue),
+    AuthorizationRequest(AuthorizationRequest),
+    AuthorizationReply(AuthorizationReply),
+    AccountingRequest(AccountingRequest),
+    AccountingReply(AccountingReply),
+}
+
+impl TacacsBody {
+    /// Decodes the body of a deobfuscated packet. The body type is chosen from the
+    /// header: the packet type, who sent it (odd sequence numbers come from the
+    /// client, even ones from the server) and, for authentication, whether it is
+    /// the first packet of the session.
+    pub fn from_packet(packet: &Packet) -> Result<Self, TacacsError> {
+        let header = packet.header();
+
+        if !header.flags.contains(TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG) {
+            return Err(TacacsError::ObfuscatedBody);
+        }
+
+        if header.seq_no == 0 {
+            return Err(TacacsError::InvalidValue { field: "seq_no", offset: 2, value: header.seq_no });
+        }
+
+        let from_client = header.seq_no % 2 == 1;
+
+        Ok(match (header.tacacs_type, from_client) {
+            (TacacsType::TacPlusAuthentication, true) if header.seq_no == 1 => TacacsBody::AuthenticationStart(AuthenticationStart::from_packet(packet)?),
+            (TacacsType::TacPlusAuthentication, true) => TacacsBody::AuthenticationContinue(AuthenticationContinue::from_packet(packet)?),
+            (TacacsType::TacPlusAuthentication, false) => TacacsBody::AuthenticationReply(AuthenticationReply::from_packet(packet)?),
+            (TacacsType::TacPlusAuthorisation, true) => TacacsBody::AuthorizationRequest(AuthorizationRequest::from_packet(packet)?),
+            (TacacsType::TacPlusAuthorisation, false) => TacacsBody::AuthorizationReply(AuthorizationReply::from_packet(packet)?),
+            (TacacsType::TacPlusAccounting, true) => TacacsBody::AccountingRequest(AccountingRequest::from_packet(packet)?),
+            (TacacsType::TacPlusAccounting, false) => TacacsBody::AccountingReply(AccountingReply::from_packet(packet)?),
+        })
+    }
+
+    pub fn tacacs_type(&self) -> TacacsType {
+        match self {
+            TacacsBody::AuthenticationStart(_) |
+            TacacsBody::AuthenticationReply(_) |
+            TacacsBody::AuthenticationContinue(_) => TacacsType::TacPlusAuthentication,
+            TacacsBody::AuthorizationRequest(_) |
+            TacacsBody::AuthorizationReply(_) => TacacsType::TacPlusAuthorisation,
+            TacacsBody::AccountingRequest(_) |
+            TacacsBody::AccountingReply(_) => TacacsType::TacPlusAccounting,
+        }
+    }
+
+    /// True for bodies sent by the client, which travel with odd sequence numbers.
+    pub fn is_from_client(&self) -> bool {
+        matches!(self,
+            TacacsBody::AuthenticationStart(_) |
+            TacacsBody::AuthenticationContinue(_) |
+            TacacsBody::AuthorizationRequest(_) |
+            TacacsBody::AccountingRequest(_))
+    }
+
+    /// The minor version a packet carrying this body starts a session with. Only an
+    /// AuthenticationStart can ask for something other than the default.
+    pub fn minor_version(&self) -> TacacsMinorVersion {
+        match self {
+            TacacsBody::AuthenticationStart(start) => start.minor_version(),
+            _ => TacacsMinorVersion::TacacsPlusMinorVerDefault,
+        }
+    }
+}
+
+impl TacacsBodyTrait for TacacsBody {
+    fn try_to_bytes(&self) -> Result<Vec<u8>, TacacsError> {
+        match self {
+            TacacsBody::AuthenticationStart(body) => body.try_to_bytes(),
+            TacacsBody::AuthenticationReply(body) => body.try_to_bytes(),
+            TacacsBody::AuthenticationContinue(body) => body.try_to_bytes(),
+            TacacsBody::AuthorizationRequest(body) => body.try_to_bytes(),
+            TacacsBody::AuthorizationReply(body) => body.try_to_bytes(),
+            TacacsBody::AccountingRequest(body) => body.try_to_bytes(),
+            TacacsBody::AccountingReply(body) => body.try_to_bytes(),
+        }
+    }
+}
+
+impl From<AuthenticationStart> for TacacsBody {
+    fn from(body: AuthenticationStart) -> Self {
+        TacacsBody::AuthenticationStart(body)
+    }
+}
+
+impl From<AuthenticationReply> for TacacsBody {
+    fn from(body: AuthenticationReply) -> Self {
+        TacacsBody::AuthenticationReply(body)
+    }
+}
+
+impl From<AuthenticationContinue> for TacacsBody {
+    fn from(body: AuthenticationContinue) -> Self {
+        TacacsBody::AuthenticationContinue(body)
+    }
+}
+
+impl From<AuthorizationRequest> for TacacsBody {
+    fn from(body: AuthorizationRequest) -> Self {
+        TacacsBody::AuthorizationRequest(body)
+    }
+}
+
+impl From<AuthorizationReply> for TacacsBody {
+    fn from(body: AuthorizationReply) -> Self {
+        TacacsBody::AuthorizationReply(body)
+    }
+}
+
+impl From<AccountingRequest> for TacacsBody {
+    fn from(body: AccountingRequest) -> Self {
+        TacacsBody::AccountingRequest(body)
+    }
+}
+
+impl From<AccountingReply> for TacacsBody {
+    fn from(body: AccountingReply) -> Self {
+        TacacsBody::AccountingReply(body)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::tacacs_string::TacacsString;
+    use super::*;
+    use crate::arguments::argument::Argument;
+    use crate::enumerations::{
+        TacacsAccountingFlags, TacacsAccountingStatus, TacacsAuthenicationReplyFlags, TacacsAuthenticationAction,
+        TacacsAuthenticationContinueFlags, TacacsAuthenticationMethod, TacacsAuthenticationService,
+        TacacsAuthenticationStatus, TacacsAuthenticationType
+    };
+
+    fn generate_start(authen_type: TacacsAuthenticationType) -> AuthenticationStart {
+        AuthenticationStart {
+            action: TacacsAuthenticationAction::TacPlusAuthenLogin,
+            priv_lvl: 1,
+            authen_type,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcLogin,
+            user: "admin".into(),
+            port: "tty0".into(),
+            rem_address: "192.0.2.1".into(),
+            data: TacacsString::new(),
+        }
+    }
+
+    fn round_trip(body: TacacsBody, seq_no: u8) -> TacacsBody {
+        let packet = Packet::from_body(&body, seq_no, 0xdeadbeef).unwrap();
+        assert_eq!(packet.header().tacacs_type, body.tacacs_type());
+        assert_eq!(packet.header().length as usize, packet.body().len());
+
+        packet.decode_body().unwrap()
+    }
+
+    #[test]
+    fn test_decode_authentication_bodies() {
+        let start = round_trip(generate_start(TacacsAuthenticationType::TacPlusAuthenTypeAscii).into(), 1);
+        assert!(matches!(start, TacacsBody::AuthenticationStart(_)));
+
+        let reply = round_trip(AuthenticationReply {
+            status: TacacsAuthenticationStatus::TacPlusAuthenStatusGetpass,
+            flags: TacacsAuthenicationReplyFlags::TAC_PLUS_AUTHEN_FLAG_NOECHO,
+            server_msg: "Password: ".into(),
+            data: TacacsString::new(),
+        }.into(), 2);
+        assert!(matches!(reply, TacacsBody::AuthenticationReply(_)));
+
+        let continuation = round_trip(AuthenticationContinue {
+            user_msg: "secret".into(),
+            data: TacacsString::new(),
+            flags: TacacsAuthenticationContinueFlags::empty(),
+        }.into(), 3);
+        assert!(matches!(continuation, TacacsBody::AuthenticationContinue(_)));
+    }
+
+    #[test]
+    fn test_decode_authorization_and_accounting_bodies() {
+        let request = round_trip(AuthorizationRequest {
+            authen_method: TacacsAuthenticationMethod::TacPlusAuthenMethodTacacsplus,
+            priv_lvl: 1,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeAscii,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcLogin,
+            user: "admin".into(),
+            port: "tty0".into(),
+            rem_address: "192.0.2.1".into(),
+            args: vec![Argument::mandatory("service", "shell")].into(),
+        }.into(), 1);
+        assert!(matches!(request, TacacsBody::AuthorizationRequest(_)));
+
+        let request = round_trip(AccountingRequest {
+            flags: TacacsAccountingFlags::START,
+            authen_method: TacacsAuthenticationMethod::TacPlusAuthenMethodTacacsplus,
+            priv_lvl: 1,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeAscii,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcLogin,
+            user: "admin".into(),
+            port: "tty0".into(),
+            rem_address: "192.0.2.1".into(),
+            args: vec![Argument::mandatory("task_id", "1")].into(),
+        }.into(), 1);
+        assert!(matches!(request, TacacsBody::AccountingRequest(_)));
+
+        let reply = round_trip(AccountingReply {
+            status: TacacsAccountingStatus::TacPlusAcctStatusSuccess,
+            server_msg: "".into(),
+            data: "".into(),
+        }.into(), 2);
+        assert!(matches!(reply, TacacsBody::AccountingReply(_)));
+    }
+
+    #[test]
+    fn test_from_body_sets_minor_version() {
+        let body = TacacsBody::from(generate_start(TacacsAuthenticationType::TacPlusAuthenTypePap));
+        let packet = Packet::from_body(&body, 1, 1).unwrap();
+
+        assert_eq!(packet.header().minor_version, TacacsMinorVersion::TacacsPlusMinorVerOne);
+        assert!(packet.header().flags.contains(TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG));
+    }
+
+    #[test]
+    fn test_decode_obfuscated_body() {
+        let body = TacacsBody::from(generate_start(TacacsAuthenticationType::TacPlusAuthenTypeAscii));
+        let packet = Packet::from_body(&body, 1, 1).unwrap().to_obfuscated(b"key").unwrap();
+
+        assert_eq!(packet.decode_body().unwrap_err(), TacacsError::ObfuscatedBody);
+    }
+
+    #[test]
+    fn test_decode_sequence_number_zero() {
+        let body = TacacsBody::from(generate_start(TacacsAuthenticationType::TacPlusAuthenTypeAscii));
+        let packet = Packet::from_body(&body, 0, 1).unwrap();
+
+        assert_eq!(packet.decode_body().unwrap_err(), TacacsError::InvalidValue { field: "seq_no", offset: 2, value: 0 });
+    }
+}
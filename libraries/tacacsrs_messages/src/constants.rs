@@ -2,4 +2,16 @@
 pub const TACACS_HEADER_LENGTH: usize = 12;
 pub const TACACS_ACCOUNTING_REQUEST_MIN_LENGTH: usize = 9;
 pub const TACACS_ACCOUNTING_ARG_SIZE_OFFSET: usize = 9;
-pub const TACACS_ACCOUNTING_REPLY_MIN_LENGTH: usize = 5;
\ No newline at end of file
+pub const TACACS_ACCOUNTING_REPLY_MIN_LENGTH: usize = 5;
+pub const TACACS_AUTHENTICATION_START_MIN_LENGTH: usize = 8;
+pub const TACACS_AUTHENTICATION_REPLY_MIN_LENGTH: usize = 6;
+pub const TACACS_AUTHENTICATION_CONTINUE_MIN_LENGTH: usize = 5;
+pub const TACACS_AUTHORIZATION_REQUEST_MIN_LENGTH: usize = 8;
+pub const TACACS_AUTHORIZATION_REQUEST_ARG_SIZE_OFFSET: usize = 8;
+pub const TACACS_AUTHORIZATION_REPLY_MIN_LENGTH: usize = 6;
+pub const TACACS_AUTHORIZATION_REPLY_ARG_SIZE_OFFSET: usize = 6;
+pub const TACACS_CHAP_RESPONSE_LENGTH: usize = 16;
+pub const TACACS_MSCHAP_CHALLENGE_LENGTH: usize = 8;
+pub const TACACS_MSCHAPV2_CHALLENGE_LENGTH: usize = 16;
+pub const TACACS_MSCHAP_RESPONSE_LENGTH: usize = 49;
+pub const TACACS_MSCHAP_NT_RESPONSE_LENGTH: usize = 24;
@@ -1,6 +1,6 @@
 use std::fmt;
 use bitflags::bitflags;
-use num_enum::TryFromPrimitive;
+use num_enum::{FromPrimitive, IntoPrimitive, TryFromPrimitive};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
@@ -58,11 +58,14 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, IntoPrimitive)]
+#[repr(u8)]
 pub enum TacacsAuthenticationAction {
     TacPlusAuthenLogin = 0x1,
     TacPlusAuthenChpass = 0x2,
     TacPlusAuthenSendauth = 0x3,
+    #[num_enum(catch_all)]
+    Unknown(u8),
 }
 
 impl fmt::Display for TacacsAuthenticationAction {
@@ -71,11 +74,12 @@ impl fmt::Display for TacacsAuthenticationAction {
             TacacsAuthenticationAction::TacPlusAuthenLogin => write!(f, "TAC_PLUS_AUTHEN_LOGIN"),
             TacacsAuthenticationAction::TacPlusAuthenChpass => write!(f, "TAC_PLUS_AUTHEN_CHPASS"),
             TacacsAuthenticationAction::TacPlusAuthenSendauth => write!(f, "TAC_PLUS_AUTHEN_SENDAUTH"),
+            TacacsAuthenticationAction::Unknown(value) => write!(f, "UNKNOWN({:#04x})", value),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub enum TacacsAuthenticationType {
     TacPlusAuthenTypeNotSet = 0x00,
@@ -84,6 +88,8 @@ pub enum TacacsAuthenticationType {
     TacPlusAuthenTypeChap = 0x3,
     TacPlusAuthenTypeMschap = 0x5,
     TacPlusAuthenTypeMschapv2 = 0x6,
+    #[num_enum(catch_all)]
+    Unknown(u8),
 }
 
 impl fmt::Display for TacacsAuthenticationType {
@@ -95,11 +101,12 @@ impl fmt::Display for TacacsAuthenticationType {
             TacacsAuthenticationType::TacPlusAuthenTypeChap => write!(f, "TAC_PLUS_AUTHEN_TYPE_CHAP"),
             TacacsAuthenticationType::TacPlusAuthenTypeMschap => write!(f, "TAC_PLUS_AUTHEN_TYPE_MSCHAP"),
             TacacsAuthenticationType::TacPlusAuthenTypeMschapv2 => write!(f, "TAC_PLUS_AUTHEN_TYPE_MSCHAPV2"),
+            TacacsAuthenticationType::Unknown(value) => write!(f, "UNKNOWN({:#04x})", value),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub enum TacacsAuthenticationService {
     TacPlusAuthenSvcNone = 0x0,
@@ -111,6 +118,8 @@ pub enum TacacsAuthenticationService {
     TacPlusAuthenSvcX25 = 0x7,
     TacPlusAuthenSvcNasi = 0x8,
     TacPlusAuthenSvcFwproxy = 0x9,
+    #[num_enum(catch_all)]
+    Unknown(u8),
 }
 
 impl fmt::Display for TacacsAuthenticationService {
@@ -125,11 +134,13 @@ impl fmt::Display for TacacsAuthenticationService {
             TacacsAuthenticationService::TacPlusAuthenSvcX25 => write!(f, "TAC_PLUS_AUTHEN_SVC_X25"),
             TacacsAuthenticationService::TacPlusAuthenSvcNasi => write!(f, "TAC_PLUS_AUTHEN_SVC_NASI"),
             TacacsAuthenticationService::TacPlusAuthenSvcFwproxy => write!(f, "TAC_PLUS_AUTHEN_SVC_FWPROXY"),
+            TacacsAuthenticationService::Unknown(value) => write!(f, "UNKNOWN({:#04x})", value),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
 pub enum TacacsAuthenticationStatus {
     TacPlusAuthenStatusPass = 0x1,
     TacPlusAuthenStatusFail = 0x2,
@@ -163,20 +174,14 @@ bitflags! {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum TacacsAuthenticationContinueStatus {
-    TacPlusContinueFlagAbort = 0x01,
-}
-
-impl fmt::Display for TacacsAuthenticationContinueStatus {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            TacacsAuthenticationContinueStatus::TacPlusContinueFlagAbort => write!(f, "TAC_PLUS_CONTINUE_FLAG_ABORT"),
-        }
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct TacacsAuthenticationContinueFlags: u8 {
+        const TAC_PLUS_CONTINUE_FLAG_ABORT = 0x01;
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub enum TacacsAuthenticationMethod {
     TacPlusAuthenMethodNotSet = 0x00,
@@ -190,6 +195,8 @@ pub enum TacacsAuthenticationMethod {
     TacPlusAuthenMethodRadius = 0x10,
     TacPlusAuthenMethodKrb4 = 0x11,
     TacPlusAuthenMethodRcmd = 0x20,
+    #[num_enum(catch_all)]
+    Unknown(u8),
 }
 
 impl fmt::Display for TacacsAuthenticationMethod {
@@ -206,11 +213,13 @@ impl fmt::Display for TacacsAuthenticationMethod {
             TacacsAuthenticationMethod::TacPlusAuthenMethodRadius => write!(f, "TAC_PLUS_AUTHEN_METH_RADIUS"),
             TacacsAuthenticationMethod::TacPlusAuthenMethodKrb4 => write!(f, "TAC_PLUS_AUTHEN_METH_KRB4"),
             TacacsAuthenticationMethod::TacPlusAuthenMethodRcmd => write!(f, "TAC_PLUS_AUTHEN_METH_RCMD"),
+            TacacsAuthenticationMethod::Unknown(value) => write!(f, "UNKNOWN({:#04x})", value),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
 pub enum TacacsAuthorizationStatus {
     TacPlusPassAdd = 0x01,
     TacPlusPassRepl = 0x02,
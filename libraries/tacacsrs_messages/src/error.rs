@@ -0,0 +1,41 @@
+use thiserror::Error;
+
+/// Errors raised while decoding or encoding TACACS+ messages.
+///
+/// Offsets are relative to the start of the slice being decoded, which is the
+/// packet for header errors and the body for everything else.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TacacsError {
+    #[error("Data too short to read {field} at offset {offset}. Needed: {needed}, Got: {got}")]
+    TooShort { field: &'static str, offset: usize, needed: usize, got: usize },
+
+    #[error("Invalid major version {0:#x}")]
+    UnknownMajorVersion(u8),
+
+    #[error("Invalid minor version {0:#x}")]
+    UnknownMinorVersion(u8),
+
+    #[error("Invalid TACACS+ type {0:#x}")]
+    UnknownType(u8),
+
+    #[error("Invalid header flags {0:#x}")]
+    InvalidHeaderFlags(u8),
+
+    #[error("Invalid {field} {value:#x} at offset {offset}")]
+    InvalidValue { field: &'static str, offset: usize, value: u8 },
+
+    #[error("Invalid {field} length. Expected: {expected}, Actual: {actual}")]
+    InvalidLength { field: &'static str, expected: usize, actual: usize },
+
+    #[error("Invalid argument '{argument}': {reason}")]
+    InvalidArgument { argument: String, reason: String },
+
+    #[error("{field} is too long to encode. Maximum: {max}, Actual: {actual}")]
+    FieldTooLong { field: &'static str, max: usize, actual: usize },
+
+    #[error("Packet body is obfuscated and must be deobfuscated before decoding")]
+    ObfuscatedBody,
+
+    #[error("Invalid body length. Expected: {expected}, Actual: {actual}")]
+    BodyLengthMismatch { expected: usize, actual: usize },
+}
@@ -1,6 +1,6 @@
 use crate::constants::TACACS_HEADER_LENGTH;
 use crate::enumerations::{TacacsMajorVersion, TacacsMinorVersion, TacacsType, TacacsFlags};
-use anyhow::Context;
+use crate::error::TacacsError;
 use num_enum::TryFromPrimitive;
 
 #[derive(Debug, Clone)]
@@ -28,29 +28,20 @@ pub struct Header {
 
 
 impl Header {
-    pub fn from_bytes(data: &[u8]) -> anyhow::Result<Self> {
+    pub fn from_bytes(data: &[u8]) -> Result<Self, TacacsError> {
         if data.len() < TACACS_HEADER_LENGTH {
-            return Err(anyhow::Error::msg("Data too short"));
+            return Err(TacacsError::TooShort { field: "header", offset: 0, needed: TACACS_HEADER_LENGTH, got: data.len() });
         }
 
-        let major_version = match TacacsMajorVersion::try_from_primitive((data[0] >> 4) & 0x0f).with_context(|| "Invalid major version. Conversion failed with error") {
-            Ok(version) => version,
-            Err(err) => return Err(err),
-        };
+        let major_version = TacacsMajorVersion::try_from_primitive((data[0] >> 4) & 0x0f).map_err(|e| TacacsError::UnknownMajorVersion(e.number))?;
 
-        let minor_version = match TacacsMinorVersion::try_from_primitive(data[0] & 0x0f).with_context(|| "Invalid minor version. Conversion failed with error") {
-            Ok(version) => version,
-            Err(err) => return Err(err),
-        };
+        let minor_version = TacacsMinorVersion::try_from_primitive(data[0] & 0x0f).map_err(|e| TacacsError::UnknownMinorVersion(e.number))?;
 
-        let tacacs_type = match TacacsType::try_from_primitive(data[1]).with_context(|| "Invalid TACACS+ type. Conversion failed with error") {
-                Ok(tacacs_type) => tacacs_type,
-                Err(err) => return Err(err),
-        };
+        let tacacs_type = TacacsType::try_from_primitive(data[1]).map_err(|e| TacacsError::UnknownType(e.number))?;
      
         let seq_no = data[2];
 
-        let flags = TacacsFlags::from_bits(data[3]).ok_or(anyhow::Error::msg("Invalid flags"))?;
+        let flags = TacacsFlags::from_bits(data[3]).ok_or(TacacsError::InvalidHeaderFlags(data[3]))?;
 
         let session_id = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
 
@@ -91,6 +82,7 @@ impl Header {
 
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     use super::*;
     use crate::enumerations::{TacacsMajorVersion, TacacsMinorVersion, TacacsType, TacacsFlags};
@@ -162,7 +154,7 @@ mod tests {
         let _header = match Header::from_bytes(binary_data_short) {
             Ok(data) => data,
             Err(e) => {
-                assert!(e.to_string().contains("Data too short"), "Error: {}", e);
+                assert_eq!(e, TacacsError::TooShort { field: "header", offset: 0, needed: TACACS_HEADER_LENGTH, got: TACACS_HEADER_LENGTH - 1 });
                 return;
             },
         };
@@ -178,7 +170,7 @@ mod tests {
         let _header = match Header::from_bytes(&binary_data) {
             Ok(data) => data,
             Err(e) => {
-                assert!(e.to_string().contains("Invalid major version"), "Error: {}", e);
+                assert_eq!(e, TacacsError::UnknownMajorVersion(0x0));
                 return;
             },
         };
@@ -194,7 +186,7 @@ mod tests {
         let _header = match Header::from_bytes(&binary_data) {
             Ok(data) => data,
             Err(e) => {
-                assert!(e.to_string().contains("Invalid minor version"), "Error: {}", e);
+                assert_eq!(e, TacacsError::UnknownMinorVersion(0x7));
                 return;
             },
         };
@@ -210,7 +202,7 @@ mod tests {
         let _header = match Header::from_bytes(&binary_data) {
             Ok(data) => data,
             Err(e) => {
-                assert!(e.to_string().contains("Invalid TACACS+ type"), "Error: {}", e);
+                assert_eq!(e, TacacsError::UnknownType(0xff));
                 return;
             },
         };
@@ -227,7 +219,7 @@ mod tests {
         let _header = match Header::from_bytes(&binary_data) {
             Ok(data) => data,
             Err(e) => {
-                assert!(e.to_string().contains("Invalid flags"), "Error: {}", e);
+                assert_eq!(e, TacacsError::InvalidHeaderFlags(flags.bits() | 0x80));
                 return;
             },
         };
@@ -250,4 +242,13 @@ mod tests {
         let binary_data_serialised = header.to_bytes();
         assert_eq!(binary_data, binary_data_serialised, "Serialised data does not match original data");
     }
-}
\ No newline at end of file
+
+    proptest::proptest! {
+        #[test]
+        fn test_from_bytes_arbitrary_data_does_not_panic(data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..32)) {
+            if let Ok(header) = Header::from_bytes(&data) {
+                proptest::prop_assert_eq!(&header.to_bytes()[..], &data[..TACACS_HEADER_LENGTH]);
+            }
+        }
+    }
+}
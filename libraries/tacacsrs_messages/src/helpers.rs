@@ -1,17 +1,157 @@
-use std::io::{Cursor, Read};
-use anyhow::Context;
+use std::io::Cursor;
+use num_enum::{FromPrimitive, TryFromPrimitive};
+use crate::arguments::argument::Argument;
+use crate::error::TacacsError;
+use crate::tacacs_string::TacacsString;
 
 
-pub fn read_string(cursor : &mut Cursor<&[u8]>, len: usize) -> Result<String, anyhow::Error> {
-    let remaining_buffer = cursor.get_ref().len() - cursor.position() as usize;
-    if remaining_buffer < len {
-        return Err(anyhow::Error::msg("Not enough data to read string. Remaining buffer too short"));
+fn take<'a>(cursor : &mut Cursor<&'a [u8]>, len: usize, field: &'static str) -> Result<&'a [u8], TacacsError> {
+    let data = *cursor.get_ref();
+    let offset = cursor.position() as usize;
+
+    let bytes = offset.checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(TacacsError::TooShort { field, offset, needed: offset.saturating_add(len), got: data.len() })?;
+
+    cursor.set_position((offset + len) as u64);
+    Ok(bytes)
+}
+
+pub fn read_u8(cursor : &mut Cursor<&[u8]>, field: &'static str) -> Result<u8, TacacsError> {
+    Ok(take(cursor, 1, field)?[0])
+}
+
+pub fn read_u16(cursor : &mut Cursor<&[u8]>, field: &'static str) -> Result<u16, TacacsError> {
+    let bytes = take(cursor, 2, field)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+pub fn read_enum<T>(cursor : &mut Cursor<&[u8]>, field: &'static str) -> Result<T, TacacsError>
+    where T: TryFromPrimitive<Primitive = u8>
+{
+    let offset = cursor.position() as usize;
+    let value = read_u8(cursor, field)?;
+
+    T::try_from_primitive(value).map_err(|_| TacacsError::InvalidValue { field, offset, value })
+}
+
+/// Reads an enum that has a catch-all variant. Values the enum does not know are
+/// kept as-is so they survive a decode and encode round trip.
+pub fn read_open_enum<T>(cursor : &mut Cursor<&[u8]>, field: &'static str) -> Result<T, TacacsError>
+    where T: FromPrimitive<Primitive = u8>
+{
+    Ok(T::from_primitive(read_u8(cursor, field)?))
+}
+
+pub fn read_flags<T>(cursor : &mut Cursor<&[u8]>, field: &'static str) -> Result<T, TacacsError>
+    where T: bitflags::Flags<Bits = u8>
+{
+    let offset = cursor.position() as usize;
+    let value = read_u8(cursor, field)?;
+
+    T::from_bits(value).ok_or(TacacsError::InvalidValue { field, offset, value })
+}
+
+pub fn read_string(cursor : &mut Cursor<&[u8]>, len: usize, field: &'static str) -> Result<TacacsString, TacacsError> {
+    Ok(TacacsString::from_bytes(take(cursor, len, field)?))
+}
+
+pub fn read_argument(cursor : &mut Cursor<&[u8]>, len: usize, field: &'static str) -> Result<Argument, TacacsError> {
+    Ok(Argument::from_bytes(take(cursor, len, field)?))
+}
+
+pub fn length_u8(len: usize, field: &'static str) -> Result<u8, TacacsError> {
+    u8::try_from(len).map_err(|_| TacacsError::FieldTooLong { field, max: u8::MAX as usize, actual: len })
+}
+
+pub fn length_u16(len: usize, field: &'static str) -> Result<[u8; 2], TacacsError> {
+    u16::try_from(len)
+        .map(u16::to_be_bytes)
+        .map_err(|_| TacacsError::FieldTooLong { field, max: u16::MAX as usize, actual: len })
+}
+
+/// Reads `count` length bytes starting at `offset`, without moving a cursor.
+/// Used by the `size_from_bytes` functions to total up variable length fields.
+pub fn lengths_at<'a>(data : &'a [u8], offset: usize, count: usize, field: &'static str) -> Result<&'a [u8], TacacsError> {
+    data.get(offset..offset + count)
+        .ok_or(TacacsError::TooShort { field, offset, needed: offset + count, got: data.len() })
+}
+
+
+/// Generates the decoding proptests shared by every body type: arbitrary input
+/// and single byte mutations of a known good body must never panic.
+#[cfg(test)]
+macro_rules! body_decoding_proptests {
+    ($body:ty, $generate_data:path) => {
+        proptest::proptest! {
+            #[test]
+            fn test_from_bytes_arbitrary_data_does_not_panic(data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..512)) {
+                let _ = <$body>::size_from_bytes(&data);
+                let _ = <$body>::from_bytes(&data);
+            }
+
+            #[test]
+            fn test_from_bytes_mutated_data_does_not_panic(
+                index in proptest::prelude::any::<proptest::sample::Index>(),
+                value in proptest::prelude::any::<u8>(),
+                length in proptest::prelude::any::<proptest::sample::Index>())
+            {
+                let mut data = $generate_data();
+                let position = index.index(data.len());
+                data[position] = value;
+                data.truncate(length.index(data.len() + 1));
+
+                let _ = <$body>::size_from_bytes(&data);
+                let _ = <$body>::from_bytes(&data);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+pub(crate) use body_decoding_proptests;
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enumerations::{TacacsAuthenticationService, TacacsAuthorizationStatus};
+
+    #[test]
+    fn test_read_string_too_short() {
+        let data: &[u8] = b"abc";
+        let mut cursor = Cursor::new(data);
+        cursor.set_position(1);
+
+        let error = read_string(&mut cursor, 3, "user").unwrap_err();
+        assert_eq!(error, TacacsError::TooShort { field: "user", offset: 1, needed: 4, got: 3 });
     }
 
-    let mut buffer = vec![0; len];
-    cursor.read_exact(&mut buffer).with_context(|| format!("Unable to read {} bytes from cursor", len))?;
+    #[test]
+    fn test_read_string_invalid_utf8_is_preserved() {
+        let data: &[u8] = &[0x61, 0xff];
+        let mut cursor = Cursor::new(data);
+
+        let string = read_string(&mut cursor, 2, "server_msg").unwrap();
+        assert_eq!(string.as_bytes(), &[0x61, 0xff]);
+    }
 
-    let string = String::from_utf8(buffer).with_context(|| "Unable to read data into UTF8 formatted string")?;
+    #[test]
+    fn test_read_enum_invalid_value() {
+        let data: &[u8] = &[0x00, 0xee];
+        let mut cursor = Cursor::new(data);
+        cursor.set_position(1);
 
-    Ok(string)
-}
\ No newline at end of file
+        let error = read_enum::<TacacsAuthorizationStatus>(&mut cursor, "status").unwrap_err();
+        assert_eq!(error, TacacsError::InvalidValue { field: "status", offset: 1, value: 0xee });
+    }
+
+    #[test]
+    fn test_read_open_enum_keeps_unknown_value() {
+        let data: &[u8] = &[0x01, 0xee];
+        let mut cursor = Cursor::new(data);
+
+        assert_eq!(read_open_enum::<TacacsAuthenticationService>(&mut cursor, "authen_service").unwrap(), TacacsAuthenticationService::TacPlusAuthenSvcLogin);
+        assert_eq!(read_open_enum::<TacacsAuthenticationService>(&mut cursor, "authen_service").unwrap(), TacacsAuthenticationService::Unknown(0xee));
+    }
+}
@@ -1,8 +1,14 @@
 pub mod header;
 pub mod packet;
+pub mod body;
 pub mod constants;
 pub mod enumerations;
 pub mod accounting;
+pub mod authentication;
+pub mod authorization;
 pub mod traits;
+pub mod error;
+pub mod tacacs_string;
+pub mod arguments;
 mod helpers;
 mod obfuscation;
\ No newline at end of file
@@ -1,20 +1,37 @@
 use md5::{Md5, Digest};
+use crate::error::TacacsError;
 use crate::header::Header;
 
 
-pub fn convert(header : &Header, data : &[u8], obfuscation_key: &[u8]) -> Vec<u8> {
+pub fn convert(header : &Header, data : &[u8], obfuscation_key: &[u8]) -> Result<Vec<u8>, TacacsError> {
+    check_length(header, data)?;
+
     let pad = generate_pad(header, obfuscation_key);
     let output : Vec<u8> = data.iter().zip(pad.iter()).map(|(a, b)| a ^ b).collect();
 
-    output
+    Ok(output)
 }
 
-pub fn convert_inplace(header : &Header, data : &mut [u8], obfuscation_key: &[u8]) {
+pub fn convert_inplace(header : &Header, data : &mut [u8], obfuscation_key: &[u8]) -> Result<(), TacacsError> {
+    check_length(header, data)?;
+
     let pad = generate_pad(header, obfuscation_key);
 
-    for (i, b) in data.iter_mut().enumerate() {
-        *b ^= pad[i];
+    for (b, p) in data.iter_mut().zip(pad.iter()) {
+        *b ^= p;
     }
+
+    Ok(())
+}
+
+// The pad is as long as the header says the body is, a shorter body means the
+// header and body have been mismatched.
+fn check_length(header : &Header, data : &[u8]) -> Result<(), TacacsError> {
+    if data.len() < header.length as usize {
+        return Err(TacacsError::BodyLengthMismatch { expected: header.length as usize, actual: data.len() });
+    }
+
+    Ok(())
 }
 
 fn generate_pad(header : &Header, obfuscation_key: &[u8]) -> Vec::<u8>
@@ -100,11 +117,30 @@ mod tests
         let obfuscation_key = b"tac_plus_key";
 
         let data = vec![0; 16];
-        let output = convert(&header, &data, obfuscation_key);
+        let output = convert(&header, &data, obfuscation_key).unwrap();
 
         assert_eq!(output.len(), 16);
     }
 
+    #[test]
+    fn test_convert_body_shorter_than_header_length()
+    {
+        let header = Header {
+            major_version : TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version : TacacsMinorVersion::TacacsPlusMinorVerOne,
+            tacacs_type : TacacsType::TacPlusAccounting,
+            seq_no : 1,
+            flags : TacacsFlags::empty(),
+            session_id : 0xdeadbeef,
+            length : 16
+        };
+
+        let mut data = vec![0; 15];
+
+        assert_eq!(convert(&header, &data, b"key").unwrap_err(), TacacsError::BodyLengthMismatch { expected: 16, actual: 15 });
+        assert_eq!(convert_inplace(&header, &mut data, b"key").unwrap_err(), TacacsError::BodyLengthMismatch { expected: 16, actual: 15 });
+    }
+
     #[test]
     fn test_decrypt()
     {
@@ -126,7 +162,7 @@ mod tests
         assert_eq!(packet.header().length, 34);
 
         let obfuscation_key = b"XX";
-        let body_data = convert(packet.header(), packet.body(), obfuscation_key);
+        let body_data = convert(packet.header(), packet.body(), obfuscation_key).unwrap();
         assert_eq!(body_data, decrypted_bytes);
 
         let decrypted_packet = packet.as_deobfuscated(obfuscation_key).unwrap();
@@ -1,6 +1,9 @@
 
 
-use crate::{constants::TACACS_HEADER_LENGTH, header::Header};
+use crate::{constants::TACACS_HEADER_LENGTH, error::TacacsError, header::Header};
+use crate::body::TacacsBody;
+use crate::enumerations::{TacacsFlags, TacacsMajorVersion};
+use crate::traits::TacacsBodyTrait;
 use crate::obfuscation::{convert, convert_inplace};
 
 pub trait PacketTrait {
@@ -15,17 +18,35 @@ pub struct Packet {
 }
 
 impl Packet {
-    pub fn new(header: Header, body: Vec<u8>) -> anyhow::Result<Self> {
+    pub fn new(header: Header, body: Vec<u8>) -> Result<Self, TacacsError> {
         if body.len() < (header.length as usize) {
-            let expected_length = header.length as usize;
-            let actual_length = body.len();
-            let error_message = format!("Invalid body length. Expected: {}, Actual: {}", expected_length, actual_length);
-            return Err(anyhow::Error::msg(error_message));
+            return Err(TacacsError::BodyLengthMismatch { expected: header.length as usize, actual: body.len() });
         }
         
         Ok(Packet { header, body })
     }
 
+    /// Builds an unobfuscated packet around a body, filling in the packet type,
+    /// version and length from the body.
+    pub fn from_body(body: &TacacsBody, seq_no: u8, session_id: u32) -> Result<Self, TacacsError> {
+        let data = body.try_to_bytes()?;
+        let header = Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: body.minor_version(),
+            tacacs_type: body.tacacs_type(),
+            seq_no,
+            flags: TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG,
+            session_id,
+            length: data.len() as u32,
+        };
+
+        Packet::new(header, data)
+    }
+
+    pub fn decode_body(&self) -> Result<TacacsBody, TacacsError> {
+        TacacsBody::from_packet(self)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::with_capacity(self.header.length as usize);
         bytes.extend_from_slice(&self.header.to_bytes());
@@ -33,9 +54,14 @@ impl Packet {
         bytes
     }
 
-    pub fn from_bytes(data : &[u8]) -> anyhow::Result<Self> {
+    pub fn from_bytes(data : &[u8]) -> Result<Self, TacacsError> {
         let header = Header::from_bytes(data)?;
-        let body = data[TACACS_HEADER_LENGTH..].to_vec();
+
+        let needed = TACACS_HEADER_LENGTH.saturating_add(header.length as usize);
+        let body = data.get(TACACS_HEADER_LENGTH..needed)
+            .ok_or(TacacsError::TooShort { field: "body", offset: TACACS_HEADER_LENGTH, needed, got: data.len() })?
+            .to_vec();
+
         Ok(Packet { header, body })
     }
 
@@ -49,8 +75,8 @@ impl Packet {
         let mut cloned_header = self.header.clone();
         cloned_header.flags.remove(crate::enumerations::TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG);
 
-        let obfuscated_body = convert(&self.header, &self.body, obfuscation_key);
-        Some(Packet::new(cloned_header, obfuscated_body).unwrap())
+        let obfuscated_body = convert(&self.header, &self.body, obfuscation_key).ok()?;
+        Packet::new(cloned_header, obfuscated_body).ok()
     }
 
     pub fn as_deobfuscated(&self, obfuscation_key : &[u8]) -> Option<Self> {
@@ -62,30 +88,30 @@ impl Packet {
         let mut cloned_header = self.header.clone();
         cloned_header.flags.insert(crate::enumerations::TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG);
 
-        let deobfuscated_body = convert(&self.header, &self.body, obfuscation_key);
-        Some(Packet::new(cloned_header, deobfuscated_body).unwrap())
+        let deobfuscated_body = convert(&self.header, &self.body, obfuscation_key).ok()?;
+        Packet::new(cloned_header, deobfuscated_body).ok()
     }
 
-    pub fn to_obfuscated(mut self, obfuscation_key : &[u8]) -> Self {
+    pub fn to_obfuscated(mut self, obfuscation_key : &[u8]) -> Result<Self, TacacsError> {
         let is_obfuscated = !self.header.flags.contains(crate::enumerations::TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG);
         match is_obfuscated {
-            true => self,
+            true => Ok(self),
             false => {
+                convert_inplace(&self.header, &mut self.body, obfuscation_key)?;
                 self.header.flags.set(crate::enumerations::TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG, false);
-                convert_inplace(&self.header, &mut self.body, obfuscation_key);
-                self
+                Ok(self)
             }
         }
     }
 
-    pub fn to_deobfuscated(mut self, obfuscation_key : &[u8]) -> Self {
+    pub fn to_deobfuscated(mut self, obfuscation_key : &[u8]) -> Result<Self, TacacsError> {
         let is_deobfuscated = self.header.flags.contains(crate::enumerations::TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG);
         match is_deobfuscated {
-            true => self,
+            true => Ok(self),
             false => {
+                convert_inplace(&self.header, &mut self.body, obfuscation_key)?;
                 self.header.flags.set(crate::enumerations::TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG, true);
-                convert_inplace(&self.header, &mut self.body, obfuscation_key);
-                self
+                Ok(self)
             }
         }
     }
@@ -103,3 +129,75 @@ impl PacketTrait for Packet {
         &self.body
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::accounting::{reply::AccountingReply, request::AccountingRequest};
+    use crate::authentication::{continuation::AuthenticationContinue, reply::AuthenticationReply, start::AuthenticationStart};
+    use crate::authorization::{reply::AuthorizationReply, request::AuthorizationRequest};
+    use crate::enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType};
+
+    fn generate_header(length: u32) -> Header {
+        Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAccounting,
+            seq_no: 1,
+            flags: TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG,
+            session_id: 0xdeadbeef,
+            length,
+        }
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let packet = Packet::new(generate_header(4), vec![1, 2, 3, 4]).unwrap();
+        let parsed = Packet::from_bytes(&packet.to_bytes()).unwrap();
+
+        assert_eq!(parsed.body(), &vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_from_bytes_body_shorter_than_header_length() {
+        let mut bytes = Packet::new(generate_header(4), vec![1, 2, 3, 4]).unwrap().to_bytes();
+        bytes.pop();
+
+        let error = Packet::from_bytes(&bytes).unwrap_err();
+        assert_eq!(error, TacacsError::TooShort { field: "body", offset: TACACS_HEADER_LENGTH, needed: TACACS_HEADER_LENGTH + 4, got: TACACS_HEADER_LENGTH + 3 });
+    }
+
+    #[test]
+    fn test_to_obfuscated_body_longer_than_header_length() {
+        let packet = Packet::new(generate_header(2), vec![1, 2, 3, 4]).unwrap();
+        let obfuscated = packet.to_obfuscated(b"key").unwrap();
+
+        assert_eq!(obfuscated.body().len(), 4);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_from_bytes_arbitrary_data_does_not_panic(data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..512)) {
+            if let Ok(packet) = Packet::from_bytes(&data) {
+                proptest::prop_assert_eq!(packet.body().len(), packet.header().length as usize);
+            }
+        }
+
+        #[test]
+        fn test_body_from_packet_arbitrary_data_does_not_panic(body in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..512)) {
+            let packet = Packet::new(generate_header(body.len() as u32), body).unwrap();
+
+            let _ = AccountingRequest::from_packet(&packet);
+            let _ = AccountingReply::from_packet(&packet);
+            let _ = AuthenticationStart::from_packet(&packet);
+            let _ = AuthenticationReply::from_packet(&packet);
+            let _ = AuthenticationContinue::from_packet(&packet);
+            let _ = AuthorizationRequest::from_packet(&packet);
+            let _ = AuthorizationReply::from_packet(&packet);
+            let _ = packet.decode_body();
+
+            let _ = packet.as_obfuscated(b"key").map(|obfuscated| obfuscated.to_deobfuscated(b"key"));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::Utf8Error;
+
+/// An RFC 8907 octet string, such as user, port, rem_addr, an argument, server_msg
+/// or data. The bytes are kept exactly as they were received, so decoding and
+/// encoding a message is byte-exact even when a NAS sends Latin-1 or binary data.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TacacsString(Vec<u8>);
+
+impl TacacsString {
+    pub fn new() -> Self {
+        TacacsString(Vec::new())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        TacacsString(bytes.to_vec())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the string if it is valid UTF-8.
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(&self.0)
+    }
+
+    /// Returns the string with any invalid UTF-8 sequences replaced by U+FFFD.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+}
+
+impl fmt::Display for TacacsString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_string_lossy())
+    }
+}
+
+impl AsRef<[u8]> for TacacsString {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<&str> for TacacsString {
+    fn from(value: &str) -> Self {
+        TacacsString(value.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for TacacsString {
+    fn from(value: String) -> Self {
+        TacacsString(value.into_bytes())
+    }
+}
+
+impl From<&String> for TacacsString {
+    fn from(value: &String) -> Self {
+        TacacsString(value.as_bytes().to_vec())
+    }
+}
+
+impl From<&[u8]> for TacacsString {
+    fn from(value: &[u8]) -> Self {
+        TacacsString(value.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for TacacsString {
+    fn from(value: Vec<u8>) -> Self {
+        TacacsString(value)
+    }
+}
+
+impl PartialEq<str> for TacacsString {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for TacacsString {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<[u8]> for TacacsString {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.0 == other
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin1_bytes_are_preserved() {
+        let string = TacacsString::from_bytes(b"tty\xe9");
+
+        assert_eq!(string.as_bytes(), b"tty\xe9");
+        assert!(string.to_str().is_err());
+        assert_eq!(string.to_string_lossy(), "tty\u{fffd}");
+    }
+
+    #[test]
+    fn test_utf8_string() {
+        let string = TacacsString::from("admin");
+
+        assert_eq!(string.to_str().unwrap(), "admin");
+        assert_eq!(string, "admin");
+        assert_eq!(string.len(), 5);
+        assert_eq!(string.to_string(), "admin");
+    }
+}
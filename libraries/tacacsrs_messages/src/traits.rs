@@ -1,5 +1,6 @@
-
+use crate::error::TacacsError;
 
 pub trait TacacsBodyTrait {
-    fn to_bytes(&self) -> Vec<u8>;
+    /// Encodes the body, failing if a field is too long for its length field.
+    fn try_to_bytes(&self) -> Result<Vec<u8>, TacacsError>;
 }
\ No newline at end of file
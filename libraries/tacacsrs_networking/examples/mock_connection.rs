@@ -10,6 +10,7 @@ use tacacsrs_messages::enumerations::*;
 use tacacsrs_messages::packet::Packet;
 use tacacsrs_messages::header::Header;
 use tacacsrs_messages::traits::TacacsBodyTrait;
+use tacacsrs_messages::arguments::argument::Argument;
 use tacacsrs_networking::mock_connection::MockConnection;
 use tacacsrs_networking::session::Session;
 use tacacsrs_networking::sessions::accounting_session::AccountingSessionTrait;
@@ -40,21 +41,21 @@ async fn main() -> anyhow::Result<()> {
         priv_lvl: 0,
         authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
         authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
-        user: "admin".to_string(),
-        port: "test".to_string(),
-        rem_address: "1.1.1.1".to_string(),
+        user: "admin".into(),
+        port: "test".into(),
+        rem_address: "1.1.1.1".into(),
         args: vec![
-            "service=shell".to_string(),
-            "task_id=123".to_string(),
-            "cmd=test".to_string()
-        ],
+            Argument::mandatory("service", "shell"),
+            Argument::mandatory("task_id", "123"),
+            Argument::mandatory("cmd", "test")
+        ].into(),
     };
 
     let accounting_reply = AccountingReply
     {
         status: TacacsAccountingStatus::TacPlusAcctStatusSuccess,
-        server_msg: "Test".to_string(),
-        data: "".to_string(),
+        server_msg: "Test".into(),
+        data: "".into(),
     };
 
     tacacs_connection.add_accounting_reply(&session, 2, &accounting_reply).await?;
@@ -76,7 +77,7 @@ impl MockConnectionAccountingSessionTrait for MockConnection
 {
     async fn add_accounting_reply(self: &Arc<Self>, session: &Session, reply_sequence_number: u8, reply: &AccountingReply) -> anyhow::Result<()>
     {
-        let data = reply.to_bytes();
+        let data = reply.try_to_bytes()?;
 
         let accounting_reply_packet = Packet::new(Header {
             major_version: TacacsMajorVersion::TacacsPlusMajor1,
@@ -3,12 +3,12 @@ use std::vec;
 
 use tacacsrs_messages::accounting::request::AccountingRequest;
 use tacacsrs_messages::enumerations::{TacacsAccountingFlags, TacacsAuthenticationMethod, TacacsAuthenticationService, TacacsAuthenticationType};
+use tacacsrs_messages::arguments::argument::Argument;
 
 use tacacsrs_networking::helpers::*;
 use tacacsrs_networking::session::Session;
 use tacacsrs_networking::sessions::accounting_session::AccountingSessionTrait;
 use tacacsrs_networking::traits::SessionManagementTrait;
-use tacacsrs_networking::tcp_connection::TcpConnectionTrait;
 use tokio::task::JoinHandle;
 
 
@@ -27,7 +27,7 @@ async fn main() -> anyhow::Result<()> {
 
     let tcp_connection = connect_tcp(hostname).await?;
     let tacacs_connection = Arc::new(
-        tacacsrs_networking::tcp_connection::TcpConnection::new(obfuscation_key.as_deref())
+        tacacsrs_networking::connection::Connection::new(obfuscation_key.as_deref())
     );
     
     tacacs_connection.run(tcp_connection).await?;
@@ -36,34 +36,25 @@ async fn main() -> anyhow::Result<()> {
     // let tcp_stream = connect_tcp(hostname).await?;
     // let tls_stream = connect_tls(tcp_stream, "tacacsserver.local").await?;
     // let tacacs_connection = Arc::new(
-    //     tacacsrs_networking::tls_connection::TlsConnection::new(obfuscation_key.as_deref())
+    //     tacacsrs_networking::connection::Connection::new(obfuscation_key.as_deref())
     // );
     // tacacs_connection.run(tls_stream).await?;
 
     let session_count = 100000;
 
-    let session_creation = (0..session_count).map(|_| {
+    // Each task creates its own session. Until the server has answered the
+    // first session's single-connect request, the others wait to be created.
+    let handles : Vec::<JoinHandle<anyhow::Result<()>>> = (0..session_count).map(|_| {
         let connection = tacacs_connection.clone();
         tokio::spawn(async move {
-            connection.create_session().await
-        })
-    });
-
-    let mut sessions = Vec::<Session>::with_capacity(session_count);
-    for session in session_creation {
-        let session = match session.await? {
-            Ok(session) => session,
-            Err(e) => {
-                println!("Failed to create session: {}", e);
-                return Err(e);
-            }
-        };
-
-        sessions.push(session);
-    }
+            let session = match connection.create_session().await {
+                Ok(session) => session,
+                Err(e) => {
+                    println!("Failed to create session: {}", e);
+                    return Err(e);
+                }
+            };
 
-    let handles : Vec::<JoinHandle<anyhow::Result<()>>> = sessions.into_iter().map(|session| {
-        tokio::spawn(async move {
             send_test_request(session).await
         })
     }).collect();
@@ -83,10 +74,10 @@ async fn send_test_request(session : Session) -> anyhow::Result<()> {
         priv_lvl: 0,
         authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
         authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
-        user: "admin".to_string(),
-        port: "test".to_string(),
-        rem_address: "1.1.1.1".to_string(),
-        args: vec!["cmd=test".to_string()],
+        user: "admin".into(),
+        port: "test".into(),
+        rem_address: "1.1.1.1".into(),
+        args: vec![Argument::mandatory("cmd", "test")].into(),
     };
 
     let _response = match session.send_accounting_request(accounting_request).await {
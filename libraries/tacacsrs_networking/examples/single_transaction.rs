@@ -4,6 +4,7 @@ use std::vec;
 
 use tacacsrs_messages::accounting::request::AccountingRequest;
 use tacacsrs_messages::enumerations::*;
+use tacacsrs_messages::arguments::argument::Argument;
 
 use tacacsrs_networking::sessions::accounting_session::AccountingSessionTrait;
 
@@ -22,7 +23,7 @@ async fn main() -> anyhow::Result<()> {
 
     let tcp_connection = tacacsrs_networking::helpers::connect_tcp(hostname).await?;
     let tacacs_connection = Arc::new(
-        tacacsrs_networking::tcp_connection::TcpConnection::new(obfuscation_key.as_deref())
+        tacacsrs_networking::connection::Connection::new(obfuscation_key.as_deref())
     );
     tacacs_connection.run(tcp_connection).await?;
 
@@ -35,14 +36,14 @@ async fn main() -> anyhow::Result<()> {
         priv_lvl: 0,
         authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
         authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
-        user: "admin".to_string(),
-        port: "test".to_string(),
-        rem_address: "1.1.1.1".to_string(),
+        user: "admin".into(),
+        port: "test".into(),
+        rem_address: "1.1.1.1".into(),
         args: vec![
-            "service=shell".to_string(),
-            "task_id=123".to_string(),
-            "cmd=test".to_string()
-        ],
+            Argument::mandatory("service", "shell"),
+            Argument::mandatory("task_id", "123"),
+            Argument::mandatory("cmd", "test")
+        ].into(),
     };
 
     let response = match session.send_accounting_request(accounting_request).await {
@@ -66,14 +67,14 @@ async fn main() -> anyhow::Result<()> {
             priv_lvl: 0,
             authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
             authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
-            user: "admin".to_string(),
-            port: "test".to_string(),
-            rem_address: "1.1.1.1".to_string(),
+            user: "admin".into(),
+            port: "test".into(),
+            rem_address: "1.1.1.1".into(),
             args: vec![
-                "service=shell".to_string(),
-                "task_id=123".to_string(),
-                "cmd=test".to_string()
-            ],
+                Argument::mandatory("service", "shell"),
+                Argument::mandatory("task_id", "123"),
+                Argument::mandatory("cmd", "test")
+            ].into(),
         }
     ).await {
         Ok(response) => response,
@@ -92,7 +93,6 @@ async fn main() -> anyhow::Result<()> {
 
 use log::{Record, Level, Metadata};
 use log::{SetLoggerError, LevelFilter};
-use tacacsrs_networking::tcp_connection::TcpConnectionTrait;
 use tacacsrs_networking::traits::SessionManagementTrait;
 static LOGGER: SimpleLogger = SimpleLogger;
 
@@ -3,11 +3,11 @@ use std::sync::Arc;
 use env_logger::Env;
 use tacacsrs_messages::accounting::request::AccountingRequest;
 use tacacsrs_messages::enumerations::*;
+use tacacsrs_messages::arguments::argument::Argument;
 use tacacsrs_networking::helpers::connect_tcp;
 
 use tacacsrs_networking::sessions::accounting_session::AccountingSessionTrait;
 use tacacsrs_networking::traits::SessionManagementTrait;
-use tacacsrs_networking::tls_connection::TLSConnectionTrait;
 
 
 
@@ -52,7 +52,7 @@ async fn main() -> anyhow::Result<()> {
     let tls_stream = connect_tls(&tls_config, tcp_stream, "tacacsserver.local").await?;
 
     let connection = Arc::new(
-        tacacsrs_networking::tls_connection::TlsConnection::new(obfuscation_key.as_deref())
+        tacacsrs_networking::connection::Connection::new(obfuscation_key.as_deref())
     );
     connection.run(tls_stream).await?;
 
@@ -65,14 +65,14 @@ async fn main() -> anyhow::Result<()> {
             priv_lvl: 0,
             authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
             authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
-            user: "admin".to_string(),
-            port: "test".to_string(),
-            rem_address: "1.1.1.1".to_string(),
+            user: "admin".into(),
+            port: "test".into(),
+            rem_address: "1.1.1.1".into(),
             args: vec![
-                "service=shell".to_string(),
-                "task_id=123".to_string(),
-                "cmd=test".to_string()
-            ],
+                Argument::mandatory("service", "shell"),
+                Argument::mandatory("task_id", "123"),
+                Argument::mandatory("cmd", "test")
+            ].into(),
         }
     ).await {
         Ok(response) => response,
@@ -0,0 +1,567 @@
+use std::future::Future;
+use std::sync::{Arc, PoisonError};
+use std::time::{Duration, Instant};
+
+use tacacsrs_messages::accounting::{reply::AccountingReply, request::AccountingRequest};
+use tacacsrs_messages::authentication::{reply::AuthenticationReply, start::AuthenticationStart};
+use tacacsrs_messages::authorization::request::AuthorizationRequest;
+use tacacsrs_messages::enumerations::{TacacsAccountingStatus, TacacsAuthenticationStatus};
+use tacacsrs_messages::error::TacacsError;
+use tacacsrs_messages::packet::Packet;
+use tokio::sync::mpsc;
+use tokio_rustls::rustls;
+
+use crate::codec::TacacsCodecError;
+use crate::connection::Connection;
+use crate::error::SessionError;
+use crate::prompter::Prompter;
+use crate::session::Session;
+use crate::sessions::accounting_session::AccountingSessionTrait;
+use crate::sessions::authentication_session::AuthenticationSessionTrait;
+use crate::sessions::authorization_session::{AuthorizationResult, AuthorizationSessionTrait};
+use crate::traits::SessionManagementTrait;
+
+/// How long a server that could not be reached is passed over by default.
+pub const DEFAULT_HOLD_DOWN: Duration = Duration::from_secs(30);
+
+/// How long a server has to accept a connection and to answer each request by default.
+pub const DEFAULT_SERVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The TLS settings for one server.
+#[derive(Debug, Clone)]
+pub struct ServerTls {
+    pub config: Arc<rustls::ClientConfig>,
+    pub domain: String,
+}
+
+/// One entry in a client's server list.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub address: String,
+    pub obfuscation_key: Option<Vec<u8>>,
+    pub tls: Option<ServerTls>,
+    pub timeout: Duration,
+}
+
+impl ServerConfig {
+    /// The address is a hostname or IP address, optionally followed by a port.
+    /// Without one the TACACS+ port, 49, is used.
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            obfuscation_key: None,
+            tls: None,
+            timeout: DEFAULT_SERVER_TIMEOUT,
+        }
+    }
+
+    pub fn with_obfuscation_key(mut self, obfuscation_key: &[u8]) -> Self {
+        self.obfuscation_key = Some(obfuscation_key.to_vec());
+        self
+    }
+
+    pub fn with_tls(mut self, config: Arc<rustls::ClientConfig>, domain: impl Into<String>) -> Self {
+        self.tls = Some(ServerTls { config, domain: domain.into() });
+        self
+    }
+
+    /// Bounds both connecting to the server and waiting for each reply.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Opens a connection to the server, giving up once the timeout passes.
+    pub async fn connect(&self) -> anyhow::Result<Arc<Connection>> {
+        let connect = async {
+            let connection = Arc::new(
+                Connection::new(self.obfuscation_key.as_deref())
+                    .with_reply_timeout(Some(self.timeout))
+            );
+
+            let stream = crate::helpers::connect_tcp(&self.address).await?;
+
+            match &self.tls {
+                Some(tls) => connection.run(crate::helpers::connect_tls(&tls.config, stream, &tls.domain).await?).await?,
+                None => connection.run(stream).await?,
+            }
+
+            anyhow::Ok(connection)
+        };
+
+        tokio::time::timeout(self.timeout, connect).await
+            .map_err(|_| anyhow::Error::msg(format!("Timed out connecting to {} after {:?}", self.address, self.timeout)))?
+    }
+}
+
+/// A server in the list, along with its open connection and hold-down deadline.
+struct Server {
+    config: ServerConfig,
+    connection: tokio::sync::Mutex<Option<Arc<Connection>>>,
+    held_down_until: std::sync::Mutex<Option<Instant>>,
+}
+
+impl Server {
+    fn is_held_down(&self, now: Instant) -> bool {
+        self.held_down_until.lock().unwrap_or_else(PoisonError::into_inner)
+            .is_some_and(|until| until > now)
+    }
+
+    fn hold_down(&self, hold_down: Duration) {
+        *self.held_down_until.lock().unwrap_or_else(PoisonError::into_inner) = Some(Instant::now() + hold_down);
+    }
+
+    /// Creates a session on the open connection, or on a new one if the server
+    /// refused single-connect or the connection has closed.
+    async fn create_session(&self) -> anyhow::Result<Session> {
+        let mut connection = self.connection.lock().await;
+
+        if let Some(open) = connection.take() {
+            if let Ok(session) = open.create_session().await {
+                *connection = Some(open);
+                return Ok(session);
+            }
+
+            // A session may still be waiting on its reply, so it gets as long as
+            // any reply would before the connection is closed.
+            let grace = self.config.timeout;
+            tokio::spawn(async move { open.shutdown(grace).await });
+        }
+
+        let open = self.config.connect().await?;
+        let session = open.create_session().await?;
+        *connection = Some(open);
+
+        Ok(session)
+    }
+
+    /// Closes and forgets the open connection.
+    async fn disconnect(&self) {
+        let connection = self.connection.lock().await.take();
+
+        if let Some(connection) = connection {
+            // The connection has usually failed already, which is why it is going.
+            let _ = connection.close().await;
+        }
+    }
+}
+
+/// Whether an exchange failed because of the server or the connection to it, in
+/// which case the next server is tried. Once the request has been sent, any
+/// error is the server's, a reply that cannot be decoded included, as it may
+/// be using a different key. Errors from before the send, such as a request
+/// that cannot be encoded, would fail the same way on every server.
+fn is_server_failure(error: &anyhow::Error) -> bool {
+    if let Some(error) = error.downcast_ref::<SessionError>() {
+        return !matches!(error, SessionError::Cancelled | SessionError::SequenceNumbersExhausted | SessionError::InvalidRequest(_));
+    }
+
+    error.downcast_ref::<std::io::Error>().is_some()
+        || error.downcast_ref::<TacacsCodecError>().is_some()
+        || error.downcast_ref::<TacacsError>().is_some()
+        || error.downcast_ref::<mpsc::error::SendError<Packet>>().is_some()
+}
+
+/// Sends requests to the first server in an ordered list that answers them.
+///
+/// RFC 8907 semantics apply: when a server cannot be reached or replies with an
+/// ERROR status the next server is tried, while any other reply, FAIL included,
+/// is final. Servers that could not be reached are held down, moving them to
+/// the back of the list until the hold-down period passes. Errors that are not
+/// the server's, such as a request with a field too long to encode, are
+/// returned straight away.
+pub struct TacacsClient {
+    servers: Vec<Server>,
+    hold_down: Duration,
+}
+
+impl TacacsClient {
+    pub fn new(servers: impl IntoIterator<Item = ServerConfig>) -> Self {
+        Self {
+            servers: servers.into_iter().map(|config| Server {
+                config,
+                connection: None.into(),
+                held_down_until: None.into(),
+            }).collect(),
+            hold_down: DEFAULT_HOLD_DOWN,
+        }
+    }
+
+    pub fn with_hold_down(mut self, hold_down: Duration) -> Self {
+        self.hold_down = hold_down;
+        self
+    }
+
+    /// Closes the open connection to each server. Later requests open new ones.
+    pub async fn close(&self) {
+        for server in &self.servers {
+            server.disconnect().await;
+        }
+    }
+
+    /// The addresses of the servers that are currently held down.
+    pub fn held_down_servers(&self) -> Vec<String> {
+        let now = Instant::now();
+        self.servers.iter()
+            .filter(|server| server.is_held_down(now))
+            .map(|server| server.config.address.clone())
+            .collect()
+    }
+
+    pub async fn authenticate<P>(&self, start: AuthenticationStart, prompter: &P) -> anyhow::Result<AuthenticationReply>
+        where P: Prompter + ?Sized
+    {
+        self.failover(
+            |session| {
+                let start = start.clone();
+                async move { session.authenticate(start, prompter).await }
+            },
+            |reply| reply.status == TacacsAuthenticationStatus::TacPlusAuthenStatusError,
+        ).await
+    }
+
+    pub async fn authorize(&self, request: AuthorizationRequest) -> anyhow::Result<AuthorizationResult> {
+        self.failover(
+            |session| {
+                let request = request.clone();
+                async move { session.send_authorization_request(request).await }
+            },
+            |result| matches!(result, AuthorizationResult::Error(_)),
+        ).await
+    }
+
+    pub async fn account(&self, request: AccountingRequest) -> anyhow::Result<AccountingReply> {
+        self.failover(
+            |session| {
+                let request = request.clone();
+                async move { session.send_accounting_request(request).await }
+            },
+            |reply| reply.status == TacacsAccountingStatus::TacPlusAcctStatusError,
+        ).await
+    }
+
+    /// Servers are tried in order, those held down last. If every server replied
+    /// with an ERROR status the last of those replies is returned.
+    async fn failover<T, F, Fut>(&self, mut exchange: F, is_server_error: impl Fn(&T) -> bool) -> anyhow::Result<T>
+    where
+        F: FnMut(Session) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let now = Instant::now();
+        let (live, held_down): (Vec<&Server>, Vec<&Server>) = self.servers.iter().partition(|server| !server.is_held_down(now));
+
+        let mut error_reply = None;
+        let mut last_error = None;
+
+        for server in live.into_iter().chain(held_down)
+        {
+            let result = match server.create_session().await {
+                Ok(session) => match exchange(session).await {
+                    Err(e) if !is_server_failure(&e) => return Err(e),
+                    result => result,
+                },
+                // Failing to connect is always the server's problem.
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(reply) if is_server_error(&reply) => {
+                    log::warn!(
+                        target: "tacacsrs_networking::client::failover",
+                        "Server {} replied with an error status, trying the next server",
+                        server.config.address
+                    );
+
+                    error_reply = Some(reply);
+                },
+                Ok(reply) => {
+                    log::info!(
+                        target: "tacacsrs_networking::client::failover",
+                        "Server {} answered",
+                        server.config.address
+                    );
+
+                    return Ok(reply);
+                },
+                Err(e) => {
+                    log::warn!(
+                        target: "tacacsrs_networking::client::failover",
+                        "Server {} failed, holding it down for {:?}: {}",
+                        server.config.address, self.hold_down, e
+                    );
+
+                    server.hold_down(self.hold_down);
+                    server.disconnect().await;
+                    last_error = Some(e);
+                },
+            }
+        }
+
+        match (error_reply, last_error) {
+            (Some(reply), _) => Ok(reply),
+            (None, Some(e)) => Err(e.context("No TACACS+ server answered")),
+            (None, None) => Err(anyhow::Error::msg("No TACACS+ servers are configured")),
+        }
+    }
+}
+
+
+impl Drop for TacacsClient {
+    /// Closes the open connections in the background. Without a runtime there is
+    /// nothing to close, as the connections' own tasks have stopped with it.
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        for connection in self.servers.iter_mut().filter_map(|server| server.connection.get_mut().take()) {
+            runtime.spawn(async move { connection.close().await });
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use futures_util::{SinkExt, StreamExt};
+    use tacacsrs_messages::arguments::argument::Argument;
+    use tacacsrs_messages::authorization::reply::AuthorizationReply;
+    use tacacsrs_messages::body::TacacsBody;
+    use tacacsrs_messages::enumerations::*;
+    use tacacsrs_messages::packet::PacketTrait;
+    use tokio::net::TcpListener;
+    use tokio_util::codec::Framed;
+
+    use crate::codec::TacacsCodec;
+    use crate::events::ConnectionState;
+
+    /// A server that answers every request with the reply body given, or closes
+    /// the connection when there is none. Returns its address and a count of the
+    /// connections it has accepted.
+    async fn spawn_server(reply: Option<TacacsBody>) -> (String, Arc<AtomicUsize>) {
+        spawn_server_with_codec(reply, TacacsCodec::new()).await
+    }
+
+    async fn spawn_server_with_codec(reply: Option<TacacsBody>, codec: TacacsCodec) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let accepted = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+
+                let Some(reply) = reply.clone() else { continue };
+                let codec = codec.clone();
+                tokio::spawn(async move {
+                    let mut framed = Framed::new(stream, codec);
+                    while let Some(Ok(request)) = framed.next().await {
+                        let header = request.header();
+                        let packet = Packet::from_body(&reply, header.seq_no + 1, header.session_id).unwrap();
+                        if framed.send(packet).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        (address, connections)
+    }
+
+    /// An address with nothing listening on it.
+    async fn unreachable_address() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    fn accounting_reply(status: TacacsAccountingStatus) -> TacacsBody {
+        TacacsBody::AccountingReply(AccountingReply { status, server_msg: "".into(), data: "".into() })
+    }
+
+    fn authorization_reply(status: TacacsAuthorizationStatus) -> TacacsBody {
+        TacacsBody::AuthorizationReply(AuthorizationReply { status, args: Default::default(), server_msg: "".into(), data: "".into() })
+    }
+
+    fn generate_accounting_request() -> AccountingRequest {
+        AccountingRequest {
+            flags: TacacsAccountingFlags::START,
+            authen_method: TacacsAuthenticationMethod::TacPlusAuthenMethodNone,
+            priv_lvl: 0,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
+            user: "admin".into(),
+            port: "tty0".into(),
+            rem_address: "127.0.0.1".into(),
+            args: vec![Argument::mandatory("service", "shell")].into(),
+        }
+    }
+
+    fn generate_authorization_request() -> AuthorizationRequest {
+        AuthorizationRequest {
+            authen_method: TacacsAuthenticationMethod::TacPlusAuthenMethodNone,
+            priv_lvl: 0,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
+            user: "admin".into(),
+            port: "tty0".into(),
+            rem_address: "127.0.0.1".into(),
+            args: vec![Argument::mandatory("service", "shell")].into(),
+        }
+    }
+
+    fn generate_server(address: String) -> ServerConfig {
+        ServerConfig::new(address).with_timeout(Duration::from_secs(2))
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_held_down() -> anyhow::Result<()> {
+        let unreachable = unreachable_address().await;
+        let (address, connections) = spawn_server(Some(accounting_reply(TacacsAccountingStatus::TacPlusAcctStatusSuccess))).await;
+
+        let client = TacacsClient::new([generate_server(unreachable.clone()), generate_server(address)]);
+
+        let reply = client.account(generate_accounting_request()).await?;
+        assert_eq!(reply.status, TacacsAccountingStatus::TacPlusAcctStatusSuccess);
+        assert_eq!(client.held_down_servers(), vec![unreachable]);
+
+        // The server does not agree to single-connect, so each request needs a new connection.
+        client.account(generate_accounting_request()).await?;
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_error_status_moves_to_next_server() -> anyhow::Result<()> {
+        let (failing, _) = spawn_server(Some(authorization_reply(TacacsAuthorizationStatus::TacPlusError))).await;
+        let (address, _) = spawn_server(Some(authorization_reply(TacacsAuthorizationStatus::TacPlusPassAdd))).await;
+
+        let client = TacacsClient::new([generate_server(failing), generate_server(address)]);
+
+        let result = client.authorize(generate_authorization_request()).await?;
+        assert!(result.is_permitted());
+
+        // A server that replied is not held down, even with an error status.
+        assert!(client.held_down_servers().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fail_status_is_final() -> anyhow::Result<()> {
+        let (denying, _) = spawn_server(Some(authorization_reply(TacacsAuthorizationStatus::TacPlusFail))).await;
+        let (address, connections) = spawn_server(Some(authorization_reply(TacacsAuthorizationStatus::TacPlusPassAdd))).await;
+
+        let client = TacacsClient::new([generate_server(denying), generate_server(address)]);
+
+        let result = client.authorize(generate_authorization_request()).await?;
+        assert!(matches!(result, AuthorizationResult::Fail(_)));
+        assert_eq!(connections.load(Ordering::SeqCst), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_all_servers_error() -> anyhow::Result<()> {
+        let (first, _) = spawn_server(Some(accounting_reply(TacacsAccountingStatus::TacPlusAcctStatusError))).await;
+        let (second, _) = spawn_server(None).await;
+
+        let client = TacacsClient::new([generate_server(first), generate_server(second.clone())]);
+
+        // The error reply from the first server is the best answer there is.
+        let reply = client.account(generate_accounting_request()).await?;
+        assert_eq!(reply.status, TacacsAccountingStatus::TacPlusAcctStatusError);
+        assert_eq!(client.held_down_servers(), vec![second]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_request_error_is_returned_without_failover() -> anyhow::Result<()> {
+        let (first, _) = spawn_server(Some(accounting_reply(TacacsAccountingStatus::TacPlusAcctStatusSuccess))).await;
+        let (second, connections) = spawn_server(Some(accounting_reply(TacacsAccountingStatus::TacPlusAcctStatusSuccess))).await;
+
+        let client = TacacsClient::new([generate_server(first), generate_server(second)]);
+
+        let mut request = generate_accounting_request();
+        request.user = "a".repeat(256).into();
+
+        let error = client.account(request).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<SessionError>(), Some(SessionError::InvalidRequest(TacacsError::FieldTooLong { .. }))));
+        assert!(client.held_down_servers().is_empty());
+        assert_eq!(connections.load(Ordering::SeqCst), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_reply_moves_to_next_server() -> anyhow::Result<()> {
+        let success = accounting_reply(TacacsAccountingStatus::TacPlusAcctStatusSuccess);
+        let (mismatched, _) = spawn_server_with_codec(Some(success.clone()), TacacsCodec::new().with_obfuscation_key(Some(b"other key"))).await;
+        let (address, _) = spawn_server_with_codec(Some(success), TacacsCodec::new().with_obfuscation_key(Some(b"shared key"))).await;
+
+        let client = TacacsClient::new([
+            generate_server(mismatched.clone()).with_obfuscation_key(b"shared key"),
+            generate_server(address).with_obfuscation_key(b"shared key"),
+        ]);
+
+        let reply = client.account(generate_accounting_request()).await?;
+        assert_eq!(reply.status, TacacsAccountingStatus::TacPlusAcctStatusSuccess);
+        assert_eq!(client.held_down_servers(), vec![mismatched]);
+        assert!(client.servers[0].connection.lock().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_close_closes_connections() -> anyhow::Result<()> {
+        let (address, _) = spawn_server(Some(accounting_reply(TacacsAccountingStatus::TacPlusAcctStatusSuccess))).await;
+
+        let client = TacacsClient::new([generate_server(address)]);
+        client.account(generate_accounting_request()).await?;
+        let connection = client.servers[0].connection.lock().await.clone().unwrap();
+
+        client.close().await;
+        assert_eq!(connection.state(), ConnectionState::Closed);
+        assert!(client.servers[0].connection.lock().await.is_none());
+
+        // The next request opens a new connection.
+        client.account(generate_accounting_request()).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drop_closes_connections() -> anyhow::Result<()> {
+        let (address, _) = spawn_server(Some(accounting_reply(TacacsAccountingStatus::TacPlusAcctStatusSuccess))).await;
+
+        let client = TacacsClient::new([generate_server(address)]);
+        client.account(generate_accounting_request()).await?;
+        let connection = client.servers[0].connection.lock().await.clone().unwrap();
+
+        drop(client);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while connection.state() != ConnectionState::Closed {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_server_answers() -> anyhow::Result<()> {
+        let client = TacacsClient::new([generate_server(unreachable_address().await)])
+            .with_hold_down(Duration::ZERO);
+
+        assert!(client.account(generate_accounting_request()).await.is_err());
+        assert!(client.held_down_servers().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+use bytes::{Buf, BytesMut};
+use tacacsrs_messages::constants::TACACS_HEADER_LENGTH;
+use tacacsrs_messages::error::TacacsError;
+use tacacsrs_messages::header::Header;
+use tacacsrs_messages::packet::Packet;
+use thiserror::Error;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// The largest body accepted by default. RFC 8907 places no limit on the length
+/// field, but no legitimate message comes anywhere near this.
+pub const DEFAULT_MAX_BODY_LENGTH: usize = 64 * 1024;
+
+/// What to do when a header fails to parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeaderErrorPolicy {
+    /// Fail the stream. The framing can no longer be trusted, so the connection
+    /// should be closed.
+    #[default]
+    Strict,
+    /// Trust the length field, skip the header and body, and carry on with the
+    /// next packet. Bodies over the maximum length still fail the stream.
+    Lenient,
+}
+
+#[derive(Debug, Error)]
+pub enum TacacsCodecError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Packet body of {length} bytes exceeds the maximum of {max} bytes")]
+    BodyTooLong { length: usize, max: usize },
+
+    #[error(transparent)]
+    Packet(#[from] TacacsError),
+}
+
+/// Frames TACACS+ packets on a byte stream. With an obfuscation key, incoming
+/// packets are deobfuscated and outgoing unobfuscated packets are obfuscated;
+/// without one, packets pass through untouched.
+#[derive(Debug, Clone)]
+pub struct TacacsCodec {
+    max_body_length: usize,
+    obfuscation_key: Option<Vec<u8>>,
+    header_error_policy: HeaderErrorPolicy,
+}
+
+impl Default for TacacsCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TacacsCodec {
+    pub fn new() -> Self {
+        Self {
+            max_body_length: DEFAULT_MAX_BODY_LENGTH,
+            obfuscation_key: None,
+            header_error_policy: HeaderErrorPolicy::Strict,
+        }
+    }
+
+    pub fn with_max_body_length(mut self, max_body_length: usize) -> Self {
+        self.max_body_length = max_body_length;
+        self
+    }
+
+    pub fn with_obfuscation_key(mut self, obfuscation_key: Option<&[u8]>) -> Self {
+        self.obfuscation_key = obfuscation_key.map(|key| key.to_vec());
+        self
+    }
+
+    pub fn with_header_error_policy(mut self, header_error_policy: HeaderErrorPolicy) -> Self {
+        self.header_error_policy = header_error_policy;
+        self
+    }
+
+    pub fn max_body_length(&self) -> usize {
+        self.max_body_length
+    }
+}
+
+impl Decoder for TacacsCodec {
+    type Item = Packet;
+    type Error = TacacsCodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            if src.len() < TACACS_HEADER_LENGTH {
+                src.reserve(TACACS_HEADER_LENGTH - src.len());
+                return Ok(None);
+            }
+
+            let header = match Header::from_bytes(&src[..TACACS_HEADER_LENGTH]) {
+                Ok(header) => Some(header),
+                Err(e) if self.header_error_policy == HeaderErrorPolicy::Lenient => {
+                    log::warn!(
+                        target: "tacacsrs_networking::codec::decode",
+                        "Skipping packet with invalid header: {}",
+                        e
+                    );
+
+                    None
+                },
+                Err(e) => return Err(e.into()),
+            };
+
+            // Checked before anything is reserved, so a forged length cannot make us allocate.
+            // The length is the last four bytes of the header.
+            let length = u32::from_be_bytes([src[8], src[9], src[10], src[11]]) as usize;
+            if length > self.max_body_length {
+                return Err(TacacsCodecError::BodyTooLong { length, max: self.max_body_length });
+            }
+
+            let frame_length = TACACS_HEADER_LENGTH + length;
+            if src.len() < frame_length {
+                src.reserve(frame_length - src.len());
+                return Ok(None);
+            }
+
+            let Some(header) = header else {
+                src.advance(frame_length);
+                continue;
+            };
+
+            src.advance(TACACS_HEADER_LENGTH);
+            let body = src.split_to(length).to_vec();
+
+            let packet = Packet::new(header, body)?;
+
+            let packet = match &self.obfuscation_key {
+                Some(key) => packet.to_deobfuscated(key)?,
+                None => packet,
+            };
+
+            return Ok(Some(packet));
+        }
+    }
+}
+
+impl Encoder<Packet> for TacacsCodec {
+    type Error = TacacsCodecError;
+
+    fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let packet = match &self.obfuscation_key {
+            Some(key) => item.to_obfuscated(key)?,
+            None => item,
+        };
+
+        dst.extend_from_slice(&packet.to_bytes());
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures_util::{SinkExt, StreamExt};
+    use tacacsrs_messages::enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType};
+    use tacacsrs_messages::packet::PacketTrait;
+    use tokio_util::codec::Framed;
+
+    fn generate_packet(body: &[u8]) -> Packet {
+        let header = Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAccounting,
+            seq_no: 1,
+            flags: TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG,
+            session_id: 0xdeadbeef,
+            length: body.len() as u32,
+        };
+
+        Packet::new(header, body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_decode_waits_for_complete_frame() {
+        let bytes = generate_packet(b"body").to_bytes();
+        let mut codec = TacacsCodec::new();
+        let mut buffer = BytesMut::new();
+
+        buffer.extend_from_slice(&bytes[..TACACS_HEADER_LENGTH - 1]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        buffer.extend_from_slice(&bytes[TACACS_HEADER_LENGTH - 1..bytes.len() - 1]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        buffer.extend_from_slice(&bytes[bytes.len() - 1..]);
+        let packet = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(packet.body(), b"body");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_decode_back_to_back_frames() {
+        let mut codec = TacacsCodec::new();
+        let mut buffer = BytesMut::new();
+        buffer.extend_from_slice(&generate_packet(b"first").to_bytes());
+        buffer.extend_from_slice(&generate_packet(b"second").to_bytes());
+
+        assert_eq!(codec.decode(&mut buffer).unwrap().unwrap().body(), b"first");
+        assert_eq!(codec.decode(&mut buffer).unwrap().unwrap().body(), b"second");
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_decode_body_too_long() {
+        let mut codec = TacacsCodec::new().with_max_body_length(8);
+        let mut buffer = BytesMut::new();
+
+        // Only the header is needed for the length to be rejected.
+        let bytes = generate_packet(b"longer than eight").to_bytes();
+        buffer.extend_from_slice(&bytes[..TACACS_HEADER_LENGTH]);
+
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert!(matches!(error, TacacsCodecError::BodyTooLong { length: 17, max: 8 }));
+    }
+
+    #[test]
+    fn test_decode_invalid_header() {
+        let mut bytes = generate_packet(b"body").to_bytes();
+        bytes[0] = 0x00;
+
+        let error = TacacsCodec::new().decode(&mut BytesMut::from(&bytes[..])).unwrap_err();
+        assert!(matches!(error, TacacsCodecError::Packet(TacacsError::UnknownMajorVersion(_))));
+    }
+
+    #[test]
+    fn test_decode_invalid_header_lenient() {
+        let mut corrupt = generate_packet(b"corrupt").to_bytes();
+        corrupt[1] = 0xff;
+
+        let mut buffer = BytesMut::from(&corrupt[..]);
+        buffer.extend_from_slice(&generate_packet(b"valid").to_bytes());
+
+        let mut codec = TacacsCodec::new().with_header_error_policy(HeaderErrorPolicy::Lenient);
+        assert_eq!(codec.decode(&mut buffer).unwrap().unwrap().body(), b"valid");
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_obfuscation_round_trip() {
+        let mut codec = TacacsCodec::new().with_obfuscation_key(Some(b"key"));
+        let mut buffer = BytesMut::new();
+
+        codec.encode(generate_packet(b"secret"), &mut buffer).unwrap();
+        assert_ne!(&buffer[TACACS_HEADER_LENGTH..], b"secret");
+
+        let obfuscated = Header::from_bytes(&buffer[..TACACS_HEADER_LENGTH]).unwrap();
+        assert!(!obfuscated.flags.contains(TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG));
+
+        let packet = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(packet.body(), b"secret");
+        assert!(packet.header().flags.contains(TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG));
+    }
+
+    #[tokio::test]
+    async fn test_framed_duplex() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = Framed::new(client, TacacsCodec::new().with_obfuscation_key(Some(b"key")));
+        let mut server = Framed::new(server, TacacsCodec::new().with_obfuscation_key(Some(b"key")));
+
+        client.send(generate_packet(b"request")).await.unwrap();
+        let packet = server.next().await.unwrap().unwrap();
+
+        assert_eq!(packet.body(), b"request");
+        assert_eq!(packet.header().session_id, 0xdeadbeef);
+    }
+}
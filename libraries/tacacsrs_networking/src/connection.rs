@@ -0,0 +1,849 @@
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use tacacsrs_messages::packet::{Packet, PacketTrait};
+
+use tokio::io::{split, AsyncRead, AsyncWrite, ReadHalf, WriteHalf};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::{self, JoinHandle};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use crate::codec::{HeaderErrorPolicy, TacacsCodec, DEFAULT_MAX_BODY_LENGTH};
+use crate::error::SessionError;
+use crate::events::{ConnectionEvent, ConnectionState};
+
+use crate::session::Session;
+use crate::session_manager::SingleConnectState;
+use crate::traits::SessionManagementTrait;
+
+/// A TACACS+ connection over any byte stream: a `TcpStream`, a TLS stream, a
+/// Unix socket or an in-memory duplex pipe.
+pub struct Connection
+{
+    connection : crate::session_manager::SessionManager,
+    obfuscation_key : Option<Vec<u8>>,
+    max_body_length : usize,
+    header_error_policy : HeaderErrorPolicy,
+
+    task : std::sync::Mutex<Option<JoinHandle<anyhow::Result<()>>>>,
+    closing : watch::Sender<bool>,
+}
+
+impl Connection
+{
+    pub fn new(obfuscation_key : Option<&[u8]>) -> Self
+    {
+        Self
+        {
+            connection: crate::session_manager::SessionManager::new(),
+            obfuscation_key: obfuscation_key.map(|key| key.to_vec()),
+            max_body_length: DEFAULT_MAX_BODY_LENGTH,
+            header_error_policy: HeaderErrorPolicy::Strict,
+            task: None.into(),
+            closing: watch::Sender::new(false),
+        }
+    }
+
+    /// Packets whose header announces a longer body close the connection
+    /// instead of being read.
+    pub fn with_max_body_length(mut self, max_body_length : usize) -> Self {
+        self.max_body_length = max_body_length;
+        self
+    }
+
+    /// By default a header that fails to parse closes the connection and fails
+    /// every open session with a protocol violation.
+    pub fn with_header_error_policy(mut self, header_error_policy : HeaderErrorPolicy) -> Self {
+        self.header_error_policy = header_error_policy;
+        self
+    }
+
+    /// The reply timeout given to every session created on this connection. Sessions
+    /// can override it with `Session::set_reply_timeout`.
+    pub fn with_reply_timeout(mut self, reply_timeout : Option<Duration>) -> Self {
+        self.connection.reply_timeout = reply_timeout;
+        self
+    }
+
+    /// Starts reading and writing packets on the stream in the background. A
+    /// connection runs on one stream only, so calling this again is an error.
+    pub async fn run<S>(self: &Arc<Self>, stream : S) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static
+    {
+        let receiver = self.connection.take_receiver().await?;
+
+        self.connection.set_state(ConnectionState::Established);
+        self.connection.emit(ConnectionEvent::Connected);
+
+        let self_clone = Arc::clone(self);
+        let task = task::spawn(async move {
+            self_clone.handle_connection(stream, receiver).await
+        });
+
+        *self.task.lock().unwrap_or_else(PoisonError::into_inner) = Some(task);
+
+        Ok(())
+    }
+
+    /// Closes the connection straight away. Sessions still waiting for a reply
+    /// fail with `SessionError::ConnectionClosed`.
+    pub async fn close(&self) -> anyhow::Result<()> {
+        self.shutdown(Duration::ZERO).await
+    }
+
+    /// Stops new sessions, then waits up to `grace` for the open ones to complete
+    /// before closing the connection. Packets already queued are written and the
+    /// stream is shut down, which for TLS sends close_notify. Returns the result
+    /// of the connection task: an error if the connection had already failed.
+    pub async fn shutdown(&self, grace : Duration) -> anyhow::Result<()> {
+        self.connection.disable_new_sessions().await;
+
+        let task = self.task.lock().unwrap_or_else(PoisonError::into_inner).take();
+        let Some(task) = task else {
+            self.connection.set_state(ConnectionState::Closed);
+            return Ok(());
+        };
+
+        if !task.is_finished() {
+            self.connection.set_state(ConnectionState::Draining);
+        }
+
+        let drained = tokio::time::timeout(grace, self.connection.sessions_drained()).await;
+
+        if drained.is_err() {
+            log::warn!(
+                target: "tacacsrs_networking::connection::shutdown",
+                "Closing the connection with {} sessions still open",
+                self.connection.active_session_count()
+            );
+        }
+
+        self.closing.send_replace(true);
+
+        task.await?
+    }
+
+    async fn handle_connection<S>(self: Arc<Self>, stream: S, receiver: mpsc::Receiver<Packet>) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static
+    {
+        let (reader, writer) = split(stream);
+        let codec = TacacsCodec::new()
+            .with_obfuscation_key(self.obfuscation_key.as_deref())
+            .with_max_body_length(self.max_body_length)
+            .with_header_error_policy(self.header_error_policy);
+        let reader = FramedRead::new(reader, codec.clone());
+        let writer = FramedWrite::new(writer, codec);
+
+        let mut write_task = {
+            let closing = self.closing.subscribe();
+
+            task::spawn(async move {
+                match Connection::write_handler(receiver, writer, closing).await
+                {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        log::error!(
+                            target: "tacacsrs_networking::connection::handle_connection",
+                            "Write task failed with error: {}",
+                            e
+                        );
+
+                        Err(e)
+                    }
+                }
+            })
+        };
+
+        let mut read_task = {
+            let self_clone = Arc::clone(&self);
+            task::spawn(async move {
+                match self_clone.read_handler(reader).await {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        log::error!(
+                            target: "tacacsrs_networking::connection::handle_connection",
+                            "Read task failed with error: {}",
+                            e
+                        );
+
+                        Err(e)
+                    }
+                }
+            })
+        };
+
+        // Neither task finishes while the connection is healthy, so once one of them stops
+        // the other is aborted and the connection is closed.
+        let result = tokio::select! {
+            write_result = &mut write_task => {
+                read_task.abort();
+                write_result
+            },
+            read_result = &mut read_task => {
+                write_task.abort();
+                read_result
+            },
+        };
+
+        // The connection is now closed, so no further sessions can be created and any
+        // session still waiting for a reply never gets one.
+        let reason = match &result {
+            Ok(Ok(())) => "connection closed by client".to_string(),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "connection task stopped".to_string(),
+        };
+        self.connection.fail_sessions(SessionError::ConnectionClosed(reason.clone())).await;
+
+        self.connection.set_state(match &result {
+            Ok(Ok(())) => ConnectionState::Closed,
+            _ => ConnectionState::Failed(reason.clone()),
+        });
+        self.connection.emit(ConnectionEvent::Disconnected(reason));
+
+        // Bubble up the error that closed the connection.
+        result?
+    }
+
+    /// Writes packets until the connection is closed, then writes whatever is still
+    /// queued and shuts the stream down.
+    async fn write_handler<W>(mut receiver : tokio::sync::mpsc::Receiver<Packet>, mut writer: FramedWrite<WriteHalf<W>, TacacsCodec>, mut closing : watch::Receiver<bool>) -> anyhow::Result<()>
+    where
+        W: AsyncWrite
+    {
+        loop {
+            let packet = tokio::select! {
+                packet = receiver.recv() => match packet {
+                    Some(packet) => packet,
+                    None => {
+                        log::error!(
+                            target: "tacacsrs_networking::connection::write_handler",
+                            "No packet received from channel"
+                        );
+
+                        return Err(anyhow::Error::msg("No packet received"))
+                    }
+                },
+                // The guard wait_for returns is not Send, so it is dropped inside the block.
+                _ = async { let _ = closing.wait_for(|closing| *closing).await; } => {
+                    while let Ok(packet) = receiver.try_recv() {
+                        writer.feed(packet).await?;
+                    }
+
+                    writer.close().await?;
+
+                    log::info!(
+                        target: "tacacsrs_networking::connection::write_handler",
+                        "Connection closed"
+                    );
+
+                    return Ok(())
+                }
+            };
+
+            let session_id = packet.header().session_id;
+
+            log::info!(
+                target: "tacacsrs_networking::connection::write_handler",
+                "Received packet for session id {} to send to network",
+                session_id
+            );
+
+            writer.send(packet).await?;
+
+            log::info!(
+                target: "tacacsrs_networking::connection::write_handler",
+                "Sent packet for session id {} to network",
+                session_id
+            );
+        }
+    }
+
+    async fn read_handler<R>(self: Arc<Self>, mut reader: FramedRead<ReadHalf<R>, TacacsCodec>) -> anyhow::Result<()>
+    where
+        R: AsyncRead
+    {
+        loop {
+            let packet = match reader.next().await {
+                Some(Ok(packet)) => packet,
+                Some(Err(e)) => {
+                    log::error!(
+                        target: "tacacsrs_networking::connection::read_handler",
+                        "Failed to read packet from network due to error: {}",
+                        e
+                    );
+
+                    let error = SessionError::from(&e);
+                    if let SessionError::ProtocolViolation(reason) = &error {
+                        self.connection.emit(ConnectionEvent::ProtocolError(reason.clone()));
+                    }
+
+                    self.connection.fail_sessions(error).await;
+
+                    return Err(e.into())
+                },
+                None => {
+                    log::error!(
+                        target: "tacacsrs_networking::connection::read_handler",
+                        "Connection closed by peer"
+                    );
+
+                    return Err(anyhow::Error::msg("Connection closed by peer"))
+                }
+            };
+
+            log::info!(
+                target: "tacacsrs_networking::connection::read_handler",
+                "Received packet for session id: {}",
+                packet.header().session_id
+            );
+
+            if let Err(e) = self.connection.send_message_to_session(packet).await {
+                log::warn!(
+                    target: "tacacsrs_networking::connection::read_handler",
+                    "Dropped packet: {}",
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SessionManagementTrait for Connection
+{
+    async fn can_create_sessions(&self) -> bool
+    {
+        self.connection.can_create_sessions().await
+    }
+
+    async fn create_session(&self) -> anyhow::Result<Session>
+    {
+        self.connection.create_session().await
+    }
+
+    fn active_session_ids(&self) -> Vec<u32>
+    {
+        self.connection.active_session_ids()
+    }
+
+    fn active_session_count(&self) -> usize
+    {
+        self.connection.active_session_count()
+    }
+
+    fn single_connect(&self) -> SingleConnectState
+    {
+        self.connection.single_connect()
+    }
+
+    fn state(&self) -> ConnectionState
+    {
+        self.connection.state()
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<ConnectionEvent>
+    {
+        self.connection.subscribe()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+    use tacacsrs_messages::enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType};
+    use tacacsrs_messages::header::Header;
+    use tacacsrs_messages::authentication::start::AuthenticationStart;
+    use tacacsrs_messages::accounting::request::AccountingRequest;
+    use tacacsrs_messages::arguments::argument::Argument;
+    use tacacsrs_messages::enumerations::{TacacsAccountingFlags, TacacsAuthenticationAction, TacacsAuthenticationMethod, TacacsAuthenticationService, TacacsAuthenticationType};
+    use tacacsrs_messages::body::TacacsBody;
+    use tokio::io::{AsyncWriteExt, DuplexStream};
+    use tokio_util::codec::Framed;
+
+    use tokio::net::{TcpListener, TcpStream};
+
+    async fn connect(tacacs_connection: Connection) -> anyhow::Result<(Arc<Connection>, DuplexStream)> {
+        let (client, server) = tokio::io::duplex(1024);
+
+        let tacacs_connection = Arc::new(tacacs_connection);
+        tacacs_connection.run(client).await?;
+
+        Ok((tacacs_connection, server))
+    }
+
+    fn generate_reply(session_id: u32) -> Packet {
+        Packet::new(Header {
+            major_version: TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAccounting,
+            seq_no: 2,
+            flags: TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG | TacacsFlags::TAC_PLUS_SINGLE_CONNECT_FLAG,
+            session_id,
+            length: 5,
+        }, vec![0, 0, 0, 0, 1]).unwrap()
+    }
+
+    async fn send_request(session: &Session) -> anyhow::Result<u8> {
+        let request = AccountingRequest {
+            flags: TacacsAccountingFlags::START,
+            authen_method: TacacsAuthenticationMethod::TacPlusAuthenMethodNone,
+            priv_lvl: 0,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
+            user: "admin".into(),
+            port: "tty0".into(),
+            rem_address: "127.0.0.1".into(),
+            args: vec![Argument::mandatory("service", "shell")].into(),
+        };
+
+        session.send(TacacsType::TacPlusAccounting, TacacsMinorVersion::TacacsPlusMinorVerDefault, &request).await
+    }
+
+    fn generate_corrupt_packet(session_id: u32) -> Vec<u8> {
+        let mut bytes = generate_reply(session_id).to_bytes();
+        bytes[1] = 0xff; // not a packet type
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_connection_over_tcp() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let stream = TcpStream::connect(listener.local_addr()?).await?;
+        let (mut server, _) = listener.accept().await?;
+
+        let tacacs_connection: Arc<dyn SessionManagementTrait> = {
+            let tacacs_connection = Arc::new(Connection::new(None));
+            tacacs_connection.run(stream).await?;
+            tacacs_connection
+        };
+
+        let session = tacacs_connection.create_session().await?;
+        send_request(&session).await?;
+        server.write_all(&generate_reply(session.session_id()).to_bytes()).await?;
+
+        let packet = tokio::time::timeout(Duration::from_secs(5), session.receive()).await??;
+        assert_eq!(packet.header().session_id, session.session_id());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_closes_connection() -> anyhow::Result<()> {
+        let (tacacs_connection, mut server) = connect(Connection::new(None).with_max_body_length(1024)).await?;
+
+        // A header announcing a body of 4 GiB.
+        server.write_all(&[0xc0, 0x03, 0x02, 0x01, 0x00, 0x00, 0x00, 0x01, 0xff, 0xff, 0xff, 0xff]).await?;
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while tacacs_connection.can_create_sessions().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_header_fails_sessions() -> anyhow::Result<()> {
+        let (tacacs_connection, mut server) = connect(Connection::new(None)).await?;
+        let session = tacacs_connection.create_session().await?;
+
+        server.write_all(&generate_corrupt_packet(session.session_id())).await?;
+        server.write_all(&generate_reply(session.session_id()).to_bytes()).await?;
+
+        let error = tokio::time::timeout(Duration::from_secs(5), session.receive()).await?.unwrap_err();
+        assert!(matches!(error.downcast_ref::<SessionError>(), Some(SessionError::ProtocolViolation(_))));
+        assert!(!tacacs_connection.can_create_sessions().await);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_header_skipped_when_lenient() -> anyhow::Result<()> {
+        let (tacacs_connection, mut server) = connect(
+            Connection::new(None).with_header_error_policy(HeaderErrorPolicy::Lenient)).await?;
+        let session = tacacs_connection.create_session().await?;
+        send_request(&session).await?;
+
+        server.write_all(&generate_corrupt_packet(session.session_id())).await?;
+        server.write_all(&generate_reply(session.session_id()).to_bytes()).await?;
+
+        let packet = tokio::time::timeout(Duration::from_secs(5), session.receive()).await??;
+        assert_eq!(packet.header().tacacs_type, TacacsType::TacPlusAccounting);
+        assert!(tacacs_connection.can_create_sessions().await);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_peer_close_fails_sessions() -> anyhow::Result<()> {
+        let (tacacs_connection, server) = connect(Connection::new(None)).await?;
+        let session = tacacs_connection.create_session().await?;
+
+        drop(server);
+
+        let error = tokio::time::timeout(Duration::from_secs(5), session.receive()).await?.unwrap_err();
+        assert!(matches!(error.downcast_ref::<SessionError>(), Some(SessionError::ConnectionClosed(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reply_timeout() -> anyhow::Result<()> {
+        let (tacacs_connection, _server) = connect(
+            Connection::new(None).with_reply_timeout(Some(Duration::from_millis(50)))).await?;
+        let session = tacacs_connection.create_session().await?;
+        assert_eq!(tacacs_connection.active_session_count(), 1);
+
+        let error = tokio::time::timeout(Duration::from_secs(5), session.receive()).await?.unwrap_err();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::Timeout(Duration::from_millis(50))));
+
+        // The timed out session is cancelled, which deregisters it.
+        assert!(session.is_complete().await);
+        assert_eq!(tacacs_connection.active_session_count(), 0);
+        assert!(tacacs_connection.can_create_sessions().await);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_session_timeout_overrides_connection() -> anyhow::Result<()> {
+        let (tacacs_connection, _server) = connect(Connection::new(None)).await?;
+        let session = tacacs_connection.create_session().await?;
+        session.set_reply_timeout(Some(Duration::from_millis(10))).await;
+
+        let error = tokio::time::timeout(Duration::from_secs(5), session.receive()).await?.unwrap_err();
+        assert!(matches!(error.downcast_ref::<SessionError>(), Some(SessionError::Timeout(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_authentication() -> anyhow::Result<()> {
+        let (tacacs_connection, server) = connect(Connection::new(None)).await?;
+        let mut server = FramedRead::new(server, TacacsCodec::new());
+        let session = Arc::new(tacacs_connection.create_session().await?);
+
+        let start = AuthenticationStart {
+            action: TacacsAuthenticationAction::TacPlusAuthenLogin,
+            priv_lvl: 1,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeAscii,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcLogin,
+            user: "user".into(),
+            port: "tty0".into(),
+            rem_address: "127.0.0.1".into(),
+            data: "".into(),
+        };
+        session.send(TacacsType::TacPlusAuthentication, TacacsMinorVersion::TacacsPlusMinorVerDefault, &start).await?;
+
+        let waiting = {
+            let session = Arc::clone(&session);
+            tokio::spawn(async move { session.receive().await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        session.cancel().await?;
+
+        let error = tokio::time::timeout(Duration::from_secs(5), waiting).await??.unwrap_err();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::Cancelled));
+
+        server.next().await.unwrap()?;
+        let abort = server.next().await.unwrap()?;
+        assert_eq!(abort.header().seq_no, 3);
+        assert!(matches!(abort.decode_body()?, TacacsBody::AuthenticationContinue(body) if body.is_abort()));
+        assert_eq!(tacacs_connection.active_session_count(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reply_validation() -> anyhow::Result<()> {
+        let (tacacs_connection, mut server) = connect(Connection::new(None)).await?;
+
+        let mut reply = generate_reply(0).to_bytes();
+        let unsolicited = tacacs_connection.create_session().await?;
+        reply[4..8].copy_from_slice(&unsolicited.session_id().to_be_bytes());
+        server.write_all(&reply).await?;
+
+        let error = tokio::time::timeout(Duration::from_secs(5), unsolicited.receive()).await?.unwrap_err();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::UnsolicitedPacket(2)));
+
+        let wrong_sequence = tacacs_connection.create_session().await?;
+        send_request(&wrong_sequence).await?;
+        reply[2] = 4;
+        reply[4..8].copy_from_slice(&wrong_sequence.session_id().to_be_bytes());
+        server.write_all(&reply).await?;
+
+        let error = tokio::time::timeout(Duration::from_secs(5), wrong_sequence.receive()).await?.unwrap_err();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::UnexpectedSequenceNumber { expected: 2, received: 4 }));
+
+        let wrong_type = tacacs_connection.create_session().await?;
+        send_request(&wrong_type).await?;
+        reply[1] = TacacsType::TacPlusAuthorisation as u8;
+        reply[2] = 2;
+        reply[4..8].copy_from_slice(&wrong_type.session_id().to_be_bytes());
+        server.write_all(&reply).await?;
+
+        let error = tokio::time::timeout(Duration::from_secs(5), wrong_type.receive()).await?.unwrap_err();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::UnexpectedPacketType {
+            expected: TacacsType::TacPlusAccounting,
+            received: TacacsType::TacPlusAuthorisation,
+        }));
+
+        // Rejected packets close their session without affecting the connection.
+        assert_eq!(tacacs_connection.active_session_count(), 0);
+        assert!(tacacs_connection.can_create_sessions().await);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_reply_dropped() -> anyhow::Result<()> {
+        let (tacacs_connection, mut server) = connect(Connection::new(None)).await?;
+        let session = tacacs_connection.create_session().await?;
+        send_request(&session).await?;
+
+        // The same reply twice: the first answers the request, the second is stale.
+        let reply = generate_reply(session.session_id()).to_bytes();
+        server.write_all(&reply).await?;
+        server.write_all(&reply).await?;
+
+        tokio::time::timeout(Duration::from_secs(5), session.receive()).await??;
+        assert_eq!(send_request(&session).await?, 3);
+
+        let error = tokio::time::timeout(Duration::from_secs(5), session.receive()).await?.unwrap_err();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::UnexpectedSequenceNumber { expected: 4, received: 2 }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_single_connect_agreed() -> anyhow::Result<()> {
+        let (tacacs_connection, server) = connect(Connection::new(None)).await?;
+        let (server_reader, mut server) = tokio::io::split(server);
+        let mut server_reader = FramedRead::new(server_reader, TacacsCodec::new());
+        assert_eq!(tacacs_connection.single_connect(), SingleConnectState::NotRequested);
+
+        let first = tacacs_connection.create_session().await?;
+        assert_eq!(tacacs_connection.single_connect(), SingleConnectState::Requested { session_id: first.session_id() });
+
+        // A second session waits until the server has answered the first.
+        let second = {
+            let tacacs_connection = Arc::clone(&tacacs_connection);
+            tokio::spawn(async move { tacacs_connection.create_session().await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!second.is_finished());
+
+        send_request(&first).await?;
+        let request = server_reader.next().await.unwrap()?;
+        assert!(request.header().flags.contains(TacacsFlags::TAC_PLUS_SINGLE_CONNECT_FLAG));
+
+        server.write_all(&generate_reply(first.session_id()).to_bytes()).await?;
+        tokio::time::timeout(Duration::from_secs(5), first.receive()).await??;
+        assert_eq!(tacacs_connection.single_connect(), SingleConnectState::Agreed);
+
+        // Only the first packet of the first session asks for single-connect.
+        let second = tokio::time::timeout(Duration::from_secs(5), second).await???;
+        send_request(&second).await?;
+        let request = server_reader.next().await.unwrap()?;
+        assert_eq!(request.header().session_id, second.session_id());
+        assert!(!request.header().flags.contains(TacacsFlags::TAC_PLUS_SINGLE_CONNECT_FLAG));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_single_connect_refused() -> anyhow::Result<()> {
+        let (tacacs_connection, mut server) = connect(Connection::new(None)).await?;
+        let session = tacacs_connection.create_session().await?;
+        send_request(&session).await?;
+
+        let mut reply = generate_reply(session.session_id()).to_bytes();
+        reply[3] = TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG.bits();
+        server.write_all(&reply).await?;
+
+        // The session that negotiated still gets its reply.
+        tokio::time::timeout(Duration::from_secs(5), session.receive()).await??;
+
+        assert_eq!(tacacs_connection.single_connect(), SingleConnectState::Refused);
+        assert!(!tacacs_connection.can_create_sessions().await);
+
+        let error = tacacs_connection.create_session().await.err().unwrap();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::SingleConnectRefused));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_sessions() -> anyhow::Result<()> {
+        let (tacacs_connection, server) = connect(Connection::new(None)).await?;
+        let mut server = Framed::new(server, TacacsCodec::new());
+        let session = tacacs_connection.create_session().await?;
+        send_request(&session).await?;
+
+        let shutdown = {
+            let tacacs_connection = Arc::clone(&tacacs_connection);
+            tokio::spawn(async move { tacacs_connection.shutdown(Duration::from_secs(5)).await })
+        };
+
+        // The open session can still be answered, but no new session can start.
+        let request = server.next().await.unwrap()?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(tacacs_connection.create_session().await.is_err());
+        assert!(!shutdown.is_finished());
+
+        server.send(generate_reply(request.header().session_id)).await?;
+        tokio::time::timeout(Duration::from_secs(5), session.receive()).await??;
+        session.complete().await;
+
+        tokio::time::timeout(Duration::from_secs(5), shutdown).await???;
+        assert!(server.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_grace_expires() -> anyhow::Result<()> {
+        let (tacacs_connection, _server) = connect(Connection::new(None)).await?;
+        let session = tacacs_connection.create_session().await?;
+        send_request(&session).await?;
+
+        tokio::time::timeout(Duration::from_secs(5), tacacs_connection.shutdown(Duration::from_millis(20))).await??;
+
+        let error = session.receive().await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<SessionError>(), Some(SessionError::ConnectionClosed(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_close_flushes_queued_packets() -> anyhow::Result<()> {
+        let (tacacs_connection, server) = connect(Connection::new(None)).await?;
+        let mut server = FramedRead::new(server, TacacsCodec::new());
+        let session = tacacs_connection.create_session().await?;
+
+        send_request(&session).await?;
+        tacacs_connection.close().await?;
+
+        let request = server.next().await.unwrap()?;
+        assert_eq!(request.header().session_id, session.session_id());
+        assert!(server.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_returns_connection_error() -> anyhow::Result<()> {
+        let (tacacs_connection, server) = connect(Connection::new(None)).await?;
+
+        drop(server);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while tacacs_connection.can_create_sessions().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await?;
+
+        let error = tacacs_connection.close().await.unwrap_err();
+        assert_eq!(error.to_string(), "Connection closed by peer");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_twice_is_an_error() -> anyhow::Result<()> {
+        let (tacacs_connection, server) = connect(Connection::new(None)).await?;
+        let mut server = FramedRead::new(server, TacacsCodec::new());
+
+        let (client, _second_server) = tokio::io::duplex(1024);
+        assert!(tacacs_connection.run(client).await.is_err());
+        assert_eq!(tacacs_connection.state(), ConnectionState::Established);
+
+        // The first stream is still the one that gets closed.
+        tacacs_connection.close().await?;
+        assert!(server.next().await.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_close_keeps_failed_state() -> anyhow::Result<()> {
+        let (tacacs_connection, server) = connect(Connection::new(None)).await?;
+
+        drop(server);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while tacacs_connection.can_create_sessions().await {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await?;
+
+        assert!(tacacs_connection.close().await.is_err());
+        assert!(matches!(tacacs_connection.state(), ConnectionState::Failed(_)));
+
+        // The task has already been collected, so this finds nothing to close.
+        tacacs_connection.close().await?;
+        assert!(matches!(tacacs_connection.state(), ConnectionState::Failed(_)));
+
+        Ok(())
+    }
+
+    async fn next_event(events: &mut broadcast::Receiver<ConnectionEvent>) -> anyhow::Result<ConnectionEvent> {
+        Ok(tokio::time::timeout(Duration::from_secs(5), events.recv()).await??)
+    }
+
+    #[tokio::test]
+    async fn test_connection_events() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(Connection::new(None));
+        let mut events = tacacs_connection.subscribe();
+        assert_eq!(tacacs_connection.state(), ConnectionState::Connecting);
+
+        let (client, mut server) = tokio::io::duplex(1024);
+        tacacs_connection.run(client).await?;
+        assert_eq!(tacacs_connection.state(), ConnectionState::Established);
+
+        let session = tacacs_connection.create_session().await?;
+        send_request(&session).await?;
+        server.write_all(&generate_reply(session.session_id()).to_bytes()).await?;
+        tokio::time::timeout(Duration::from_secs(5), session.receive()).await??;
+        assert_eq!(tacacs_connection.state(), ConnectionState::SingleConnect);
+
+        let session_id = session.session_id();
+        drop(session);
+        tacacs_connection.close().await?;
+        assert_eq!(tacacs_connection.state(), ConnectionState::Closed);
+
+        assert_eq!(next_event(&mut events).await?, ConnectionEvent::Connected);
+        assert_eq!(next_event(&mut events).await?, ConnectionEvent::SessionOpened(session_id));
+        assert_eq!(next_event(&mut events).await?, ConnectionEvent::SingleConnectNegotiated { agreed: true });
+        assert_eq!(next_event(&mut events).await?, ConnectionEvent::SessionClosed(session_id));
+        assert_eq!(next_event(&mut events).await?, ConnectionEvent::Disconnected("connection closed by client".to_string()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_protocol_error_events() -> anyhow::Result<()> {
+        let (tacacs_connection, mut server) = connect(Connection::new(None)).await?;
+        let mut events = tacacs_connection.subscribe();
+        let session = tacacs_connection.create_session().await?;
+        assert_eq!(next_event(&mut events).await?, ConnectionEvent::SessionOpened(session.session_id()));
+
+        server.write_all(&generate_corrupt_packet(session.session_id())).await?;
+
+        assert!(matches!(next_event(&mut events).await?, ConnectionEvent::ProtocolError(_)));
+        assert_eq!(next_event(&mut events).await?, ConnectionEvent::SessionClosed(session.session_id()));
+        let ConnectionEvent::Disconnected(reason) = next_event(&mut events).await? else {
+            panic!("Expected the connection to disconnect");
+        };
+
+        assert_eq!(tacacs_connection.state(), ConnectionState::Failed(reason));
+
+        Ok(())
+    }
+}
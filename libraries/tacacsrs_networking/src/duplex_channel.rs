@@ -1,16 +1,22 @@
 use tacacsrs_messages::packet::Packet;
 use tokio::sync::RwLock;
 
+use crate::error::SessionError;
+
+/// What the connection routes to a session: a packet, or the reason the
+/// connection gave up on it.
+pub type SessionMessage = Result<Packet, SessionError>;
+
 pub struct DuplexChannel
 {
     pub sender: tokio::sync::mpsc::Sender<Packet>,
-    pub receiver: RwLock<tokio::sync::mpsc::Receiver<Packet>>,
+    pub receiver: RwLock<tokio::sync::mpsc::Receiver<SessionMessage>>,
 }
 
 
 impl DuplexChannel
 {
-    pub fn new(session_receiver: tokio::sync::mpsc::Receiver<Packet>, tcp_sender: tokio::sync::mpsc::Sender<Packet>) -> Self
+    pub fn new(session_receiver: tokio::sync::mpsc::Receiver<SessionMessage>, tcp_sender: tokio::sync::mpsc::Sender<Packet>) -> Self
     {
         Self
         {
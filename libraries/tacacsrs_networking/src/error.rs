@@ -0,0 +1,57 @@
+use std::time::Duration;
+use tacacsrs_messages::enumerations::TacacsType;
+use tacacsrs_messages::error::TacacsError;
+use thiserror::Error;
+
+use crate::codec::TacacsCodecError;
+
+/// Why a session could not complete its exchange. Session methods return these
+/// wrapped in `anyhow::Error`, so callers can `downcast_ref::<SessionError>()`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SessionError {
+    #[error("Protocol violation: {0}")]
+    ProtocolViolation(String),
+
+    #[error("Connection closed: {0}")]
+    ConnectionClosed(String),
+
+    #[error("No reply received within {0:?}")]
+    Timeout(Duration),
+
+    #[error("Session was cancelled")]
+    Cancelled,
+
+    #[error("Expected a reply with sequence number {expected}, received {received}")]
+    UnexpectedSequenceNumber { expected: u8, received: u8 },
+
+    #[error("Expected a {expected} reply, received a {received} packet")]
+    UnexpectedPacketType { expected: TacacsType, received: TacacsType },
+
+    #[error("Received a packet with sequence number {0} when no reply was expected")]
+    UnsolicitedPacket(u8),
+
+    #[error("{0}")]
+    UnexpectedBody(String),
+
+    #[error("Request could not be encoded: {0}")]
+    InvalidRequest(TacacsError),
+
+    #[error("Sequence numbers are exhausted, the session must be restarted")]
+    SequenceNumbersExhausted,
+
+    #[error("No session with id {0:#010x}")]
+    UnknownSession(u32),
+
+    #[error("The server refused single-connect mode, open a new connection for each session")]
+    SingleConnectRefused,
+}
+
+impl From<&TacacsCodecError> for SessionError {
+    fn from(error: &TacacsCodecError) -> Self {
+        match error {
+            TacacsCodecError::Io(_) => SessionError::ConnectionClosed(error.to_string()),
+            TacacsCodecError::BodyTooLong { .. } |
+            TacacsCodecError::Packet(_) => SessionError::ProtocolViolation(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+/// How many events a subscriber can fall behind before it starts missing them.
+pub const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// The lifecycle of a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Created, but not yet running on a stream.
+    Connecting,
+    /// Running. Until the first reply arrives it is not known whether the server
+    /// will allow more than one session.
+    Established,
+    /// Running, and the server agreed to carry several sessions on it.
+    SingleConnect,
+    /// Shutting down: no new sessions, waiting for the open ones to complete.
+    Draining,
+    /// Closed by the client.
+    Closed,
+    /// Closed by the peer or by an error, with the reason.
+    Failed(String),
+}
+
+impl ConnectionState {
+    /// Closed and failed connections are never reopened.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ConnectionState::Closed | ConnectionState::Failed(_))
+    }
+}
+
+/// Published on a connection's broadcast channel as things happen to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    Connected,
+    SingleConnectNegotiated { agreed: bool },
+    SessionOpened(u32),
+    SessionClosed(u32),
+    /// The server sent something that could not be accepted. If the framing was
+    /// lost, a `Disconnected` event follows.
+    ProtocolError(String),
+    Disconnected(String),
+}
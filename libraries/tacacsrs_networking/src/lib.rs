@@ -3,8 +3,13 @@ pub mod session;
 pub mod sessions;
 pub mod duplex_channel;
 pub mod session_manager;
-pub mod tls_connection;
-pub mod tcp_connection;
+pub mod connection;
 pub mod mock_connection;
 pub mod helpers;
 pub mod traits;
+pub mod prompter;
+pub mod codec;
+pub mod error;
+pub mod client;
+pub mod pool;
+pub mod events;
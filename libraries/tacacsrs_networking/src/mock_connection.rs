@@ -4,12 +4,17 @@ use tokio::sync::RwLock;
 use std::sync::Arc;
 use tacacsrs_messages::{
     accounting::reply::AccountingReply,
+    authentication::reply::AuthenticationReply,
+    authorization::reply::AuthorizationReply,
     enumerations::*,
     header::Header,
     packet::Packet, packet::PacketTrait,
     traits::TacacsBodyTrait};
 
-use crate::{session::Session, session_manager::SessionManager, traits::SessionManagementTrait};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::events::{ConnectionEvent, ConnectionState};
+use crate::{session::Session, session_manager::{SessionManager, SingleConnectState}, traits::SessionManagementTrait};
 
 
 #[derive(Debug)]
@@ -48,10 +53,15 @@ impl MockConnection {
     }
 
     pub async fn run(self : &Arc<Self>) -> anyhow::Result<()> {
+        let receiver = self.connection.take_receiver().await?;
+
         info!("Starting!");
+        self.connection.set_state(ConnectionState::Established);
+        self.connection.emit(ConnectionEvent::Connected);
+
         let self_clone = self.clone();
         let run_task_future = async move {
-            let result = match self_clone.handle_connection().await{
+            let result = match self_clone.handle_connection(receiver).await{
                 Ok(_) => Ok(()),
                 Err(e) => {
                     info!("Handler exited with Error: {:?}", e);
@@ -59,7 +69,7 @@ impl MockConnection {
                 },
             };
 
-            self_clone.connection.duplex_channels.write().await.clear();
+            self_clone.connection.duplex_channels.lock().unwrap().clear();
 
             result
         };
@@ -70,9 +80,7 @@ impl MockConnection {
         Ok(())
     }
 
-    async fn handle_connection(self: &Arc<Self>) -> anyhow::Result<()> {
-        let mut receiver = self.connection.receiver.lock().await.take().unwrap();
-
+    async fn handle_connection(self: &Arc<Self>, mut receiver: mpsc::Receiver<Packet>) -> anyhow::Result<()> {
         info!("Handling connection!");
 
         loop {
@@ -141,7 +149,7 @@ impl MockConnection {
                         "Failed to send reply for session {} with sequence number {} due to error: {}",
                         session_id,
                         reply_sequence_number,
-                        e.to_string()
+                        e
                     );
                 }
             }
@@ -167,21 +175,36 @@ impl MockConnection {
         Ok(())
     }
 
-    pub async fn add_accounting_reply(self: &Arc<Self>, session: &Session, reply_sequence_number: u8, reply: &AccountingReply) -> anyhow::Result<()>
+    async fn add_body_reply(self: &Arc<Self>, session: &Session, reply_sequence_number: u8, tacacs_type: TacacsType, reply: &dyn TacacsBodyTrait) -> anyhow::Result<()>
     {
-        let data = reply.to_bytes();
+        let data = reply.try_to_bytes()?;
 
-        let accounting_reply_packet = Packet::new(Header {
+        let reply_packet = Packet::new(Header {
             major_version: TacacsMajorVersion::TacacsPlusMajor1,
             minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
-            tacacs_type: TacacsType::TacPlusAccounting,
+            tacacs_type,
             seq_no: reply_sequence_number,
             flags: TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG,
             session_id: session.session_id(),
             length: data.len() as u32,
-        }, data).unwrap();
+        }, data)?;
     
-        self.add_reply(accounting_reply_packet).await
+        self.add_reply(reply_packet).await
+    }
+
+    pub async fn add_accounting_reply(self: &Arc<Self>, session: &Session, reply_sequence_number: u8, reply: &AccountingReply) -> anyhow::Result<()>
+    {
+        self.add_body_reply(session, reply_sequence_number, TacacsType::TacPlusAccounting, reply).await
+    }
+
+    pub async fn add_authentication_reply(self: &Arc<Self>, session: &Session, reply_sequence_number: u8, reply: &AuthenticationReply) -> anyhow::Result<()>
+    {
+        self.add_body_reply(session, reply_sequence_number, TacacsType::TacPlusAuthentication, reply).await
+    }
+
+    pub async fn add_authorization_reply(self: &Arc<Self>, session: &Session, reply_sequence_number: u8, reply: &AuthorizationReply) -> anyhow::Result<()>
+    {
+        self.add_body_reply(session, reply_sequence_number, TacacsType::TacPlusAuthorisation, reply).await
     }
 
     pub async fn get_replies_for_session(self : &Arc<Self>, session_id: u32) -> anyhow::Result<std::collections::HashMap<u8, tacacsrs_messages::packet::Packet>>
@@ -210,14 +233,39 @@ impl MockConnection {
 #[async_trait]
 impl SessionManagementTrait for MockConnection
 {
-    async fn can_create_sessions(self : &Arc<Self>) -> bool
+    async fn can_create_sessions(&self) -> bool
     {
         self.connection.can_create_sessions().await
     }
 
-    async fn create_session(self : &Arc<Self>) -> anyhow::Result<Session>
+    async fn create_session(&self) -> anyhow::Result<Session>
     {
         self.connection.create_session().await
     }
+
+    fn active_session_ids(&self) -> Vec<u32>
+    {
+        self.connection.active_session_ids()
+    }
+
+    fn active_session_count(&self) -> usize
+    {
+        self.connection.active_session_count()
+    }
+
+    fn single_connect(&self) -> SingleConnectState
+    {
+        self.connection.single_connect()
+    }
+
+    fn state(&self) -> ConnectionState
+    {
+        self.connection.state()
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<ConnectionEvent>
+    {
+        self.connection.subscribe()
+    }
 }
 
@@ -0,0 +1,600 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, PoisonError};
+use std::time::Duration;
+
+use tacacsrs_messages::accounting::request::AccountingRequest;
+use futures_util::future::join_all;
+use tacacsrs_messages::enumerations::TacacsAccountingStatus;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::client::ServerConfig;
+use crate::connection::Connection;
+use crate::events::ConnectionEvent;
+use crate::session::Session;
+use crate::session_manager::SingleConnectState;
+use crate::sessions::accounting_session::AccountingSessionTrait;
+use crate::traits::SessionManagementTrait;
+
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// One of the pool's connections. Empty while it is being (re)established, and
+/// not handed out until it has passed a probe.
+#[derive(Default)]
+struct Slot {
+    state: std::sync::Mutex<SlotState>,
+}
+
+#[derive(Default)]
+struct SlotState {
+    connection: Option<Arc<Connection>>,
+    healthy: bool,
+}
+
+impl Slot {
+    fn get(&self) -> Option<Arc<Connection>> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.connection.clone().filter(|_| state.healthy)
+    }
+
+    /// Holds a connection that has yet to pass a probe, so that it is closed with
+    /// the pool even before it is handed out.
+    fn open(&self, connection: Arc<Connection>) {
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = SlotState { connection: Some(connection), healthy: false };
+    }
+
+    fn set_healthy(&self) {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).healthy = true;
+    }
+
+    fn take(&self) -> Option<Arc<Connection>> {
+        std::mem::take(&mut *self.state.lock().unwrap_or_else(PoisonError::into_inner)).connection
+    }
+}
+
+/// How a slot keeps its connection alive. Cloned into each slot's task.
+#[derive(Clone)]
+struct Maintenance {
+    server: ServerConfig,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    health_check_interval: Duration,
+    health_check: Option<AccountingRequest>,
+}
+
+impl Maintenance {
+    /// Exponential backoff with equal jitter: half of the delay is fixed and half
+    /// random, so slots that failed together do not all retry together.
+    fn backoff(&self, failures: u32) -> Duration {
+        let delay = self.initial_backoff
+            .saturating_mul(2_u32.saturating_pow(failures.saturating_sub(1)))
+            .min(self.max_backoff);
+
+        delay / 2 + delay.mul_f64(rand::random::<f64>() / 2.0)
+    }
+
+    /// A connection is healthy while its tasks are running and, if a probe is
+    /// configured, the server answers it. A connection the server will not
+    /// share is of no use to the pool.
+    async fn probe(&self, connection: &Connection) -> anyhow::Result<()> {
+        if !connection.can_create_sessions().await {
+            return Err(anyhow::Error::msg("Connection is closed"));
+        }
+
+        let Some(request) = &self.health_check else {
+            return Ok(());
+        };
+
+        let reply = connection.create_session().await?.send_accounting_request(request.clone()).await?;
+        if reply.status == TacacsAccountingStatus::TacPlusAcctStatusError {
+            return Err(anyhow::Error::msg(format!("Health check failed: {}", reply.server_msg.to_string_lossy())));
+        }
+
+        if connection.single_connect() == SingleConnectState::Refused {
+            return Err(anyhow::Error::msg("Server refused single-connect mode"));
+        }
+
+        Ok(())
+    }
+
+    async fn run(self, slot: Arc<Slot>) {
+        let mut failures = 0;
+
+        loop {
+            if failures > 0 {
+                tokio::time::sleep(self.backoff(failures)).await;
+            }
+
+            let connection = match self.server.connect().await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    failures += 1;
+
+                    log::warn!(
+                        target: "tacacsrs_networking::pool::maintain",
+                        "Failed to connect to {} (attempt {}): {}",
+                        self.server.address, failures, e
+                    );
+
+                    continue;
+                }
+            };
+
+            let mut events = connection.subscribe();
+            slot.open(Arc::clone(&connection));
+
+            // Only connections that have passed a probe are handed out.
+            if let Err(e) = self.probe(&connection).await {
+                failures += 1;
+
+                log::warn!(
+                    target: "tacacsrs_networking::pool::maintain",
+                    "New connection to {} is unhealthy (attempt {}): {}",
+                    self.server.address, failures, e
+                );
+
+                let _ = Self::retire(slot.take(), Duration::ZERO).await;
+                continue;
+            }
+
+            log::info!(
+                target: "tacacsrs_networking::pool::maintain",
+                "Connected to {}",
+                self.server.address
+            );
+
+            slot.set_healthy();
+
+            let grace = loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(self.health_check_interval) => {},
+                    _ = Self::single_connect_refused(&connection, &mut events) => {
+                        log::warn!(
+                            target: "tacacsrs_networking::pool::maintain",
+                            "Server {} refused single-connect mode, reconnecting",
+                            self.server.address
+                        );
+
+                        // The session that was refused is left to finish.
+                        break self.server.timeout;
+                    },
+                }
+
+                if let Err(e) = self.probe(&connection).await {
+                    log::warn!(
+                        target: "tacacsrs_networking::pool::maintain",
+                        "Connection to {} is unhealthy, reconnecting: {}",
+                        self.server.address, e
+                    );
+
+                    break Duration::ZERO;
+                }
+            };
+
+            // Back off before reconnecting, in case the server has gone away.
+            let _ = Self::retire(slot.take(), grace).await;
+            failures = 1;
+        }
+    }
+
+    /// Resolves once the server has refused single-connect on the connection.
+    /// Without a health check this is how a refusal is noticed, as the first
+    /// session on the connection is then one handed out by the pool.
+    async fn single_connect_refused(connection: &Connection, events: &mut broadcast::Receiver<ConnectionEvent>) {
+        // Every event, a missed one included, is a cue to look at the state again.
+        while connection.single_connect() != SingleConnectState::Refused {
+            if let Err(broadcast::error::RecvError::Closed) = events.recv().await {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    /// Closes a connection the pool has given up on, giving its sessions up to
+    /// `grace` to complete. Its task holds a reference of its own, so dropping it
+    /// would leave the socket open. The close runs on a task of its own so that
+    /// stopping the pool cannot interrupt it.
+    fn retire(connection: Option<Arc<Connection>>, grace: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Some(connection) = connection {
+                // A connection that failed returns the reason, which has already been logged.
+                let _ = connection.shutdown(grace).await;
+            }
+        })
+    }
+}
+
+/// Stops the tasks looking after the slots, then shuts down the connections they
+/// hold. The tasks are waited on so that none can open another connection after.
+async fn close_slots(tasks: Vec<JoinHandle<()>>, slots: &[Arc<Slot>], grace: Duration) {
+    for task in &tasks {
+        task.abort();
+    }
+
+    for task in tasks {
+        let _ = task.await;
+    }
+
+    let retiring = slots.iter().map(|slot| Maintenance::retire(slot.take(), grace));
+    join_all(retiring).await;
+}
+
+/// Keeps a number of single-connect connections open to one server and hands out
+/// sessions from the healthy ones. Each connection is looked after by a background
+/// task that reconnects with exponential backoff whenever it closes, fails a
+/// health check or turns out not to be single-connect. Dropping the pool closes
+/// its connections in the background; `shutdown` does so and waits.
+pub struct ConnectionPool {
+    maintenance: Maintenance,
+    slots: Vec<Arc<Slot>>,
+    next_slot: AtomicUsize,
+    tasks: std::sync::Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl ConnectionPool {
+    pub fn new(server: ServerConfig, size: usize) -> Self {
+        Self {
+            maintenance: Maintenance {
+                server,
+                initial_backoff: DEFAULT_INITIAL_BACKOFF,
+                max_backoff: DEFAULT_MAX_BACKOFF,
+                health_check_interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+                health_check: None,
+            },
+            slots: (0..size).map(|_| Arc::default()).collect(),
+            next_slot: AtomicUsize::new(0),
+            tasks: Vec::new().into(),
+        }
+    }
+
+    pub fn with_backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.maintenance.initial_backoff = initial_backoff;
+        self.maintenance.max_backoff = max_backoff;
+        self
+    }
+
+    /// How often each connection is checked. Without a health check request only
+    /// the connection itself is checked, so a server that has stopped answering
+    /// goes unnoticed until a session times out.
+    pub fn with_health_check_interval(mut self, health_check_interval: Duration) -> Self {
+        self.maintenance.health_check_interval = health_check_interval;
+        self
+    }
+
+    /// An accounting request sent as a probe on every health check, typically a
+    /// WATCHDOG record for a test user. A reply other than ERROR is healthy.
+    /// The first probe also settles single-connect before the connection is
+    /// handed out. Without one, the first session handed out settles it, and a
+    /// connection the server refuses is replaced once that session completes.
+    pub fn with_health_check(mut self, request: AccountingRequest) -> Self {
+        self.maintenance.health_check = Some(request);
+        self
+    }
+
+    /// Starts the background tasks that open and look after the connections.
+    /// Calling it again has no effect.
+    pub fn start(&self) {
+        let mut tasks = self.tasks.lock().unwrap_or_else(PoisonError::into_inner);
+        if !tasks.is_empty() {
+            return;
+        }
+
+        for slot in &self.slots {
+            tasks.push(tokio::spawn(self.maintenance.clone().run(Arc::clone(slot))));
+        }
+    }
+
+    /// Stops looking after the connections and closes them straight away.
+    pub async fn close(&self) {
+        self.shutdown(Duration::ZERO).await
+    }
+
+    /// Stops looking after the connections, then closes them, giving open sessions
+    /// up to `grace` to complete. The pool can be started again afterwards.
+    pub async fn shutdown(&self, grace: Duration) {
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(PoisonError::into_inner));
+        close_slots(tasks, &self.slots, grace).await;
+    }
+
+    /// The number of connections that are open and passed their last health check.
+    pub async fn healthy_connection_count(&self) -> usize {
+        let mut count = 0;
+        for connection in self.slots.iter().filter_map(|slot| slot.get()) {
+            if connection.can_create_sessions().await {
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    /// Creates a session on the next healthy connection, round robin.
+    pub async fn create_session(&self) -> anyhow::Result<Session> {
+        let start = self.next_slot.fetch_add(1, Ordering::Relaxed);
+
+        for index in 0..self.slots.len() {
+            let Some(connection) = self.slots[(start + index) % self.slots.len()].get() else {
+                continue;
+            };
+
+            if let Ok(session) = connection.create_session().await {
+                return Ok(session);
+            }
+        }
+
+        Err(anyhow::Error::msg(format!("No healthy connection to {}", self.maintenance.server.address)))
+    }
+}
+
+impl Drop for ConnectionPool {
+    /// Closes the connections in the background. Without a runtime there is
+    /// nothing to close, as the connections' own tasks have stopped with it.
+    fn drop(&mut self) {
+        let tasks = std::mem::take(self.tasks.get_mut().unwrap_or_else(PoisonError::into_inner));
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                let slots = std::mem::take(&mut self.slots);
+                runtime.spawn(async move { close_slots(tasks, &slots, Duration::ZERO).await });
+            },
+            Err(_) => tasks.iter().for_each(JoinHandle::abort),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures_util::{SinkExt, StreamExt};
+    use tacacsrs_messages::accounting::reply::AccountingReply;
+    use tacacsrs_messages::arguments::argument::Argument;
+    use tacacsrs_messages::body::TacacsBody;
+    use tacacsrs_messages::enumerations::*;
+    use tacacsrs_messages::packet::{Packet, PacketTrait};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_util::codec::Framed;
+
+    use crate::codec::TacacsCodec;
+    use crate::events::ConnectionState;
+
+    /// A single-connect server that answers every accounting request with the
+    /// given status. Each accepted connection is announced on the channel, and
+    /// dropping the sender it carries closes that connection.
+    async fn spawn_server(status: TacacsAccountingStatus) -> (String, mpsc::UnboundedReceiver<mpsc::Sender<()>>) {
+        spawn_switchable_server(Arc::new(status.into()), true).await
+    }
+
+    /// As `spawn_server`, with a status that can be changed while it runs and a
+    /// choice of whether to agree to single-connect.
+    async fn spawn_switchable_server(status: Arc<std::sync::Mutex<TacacsAccountingStatus>>, single_connect: bool) -> (String, mpsc::UnboundedReceiver<mpsc::Sender<()>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let (accepted, connections) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (close, mut closed) = mpsc::channel::<()>(1);
+                if accepted.send(close).is_err() {
+                    break;
+                }
+
+                let status = Arc::clone(&status);
+                tokio::spawn(async move {
+                    let mut framed = Framed::new(stream, TacacsCodec::new());
+
+                    loop {
+                        let request = tokio::select! {
+                            request = framed.next() => match request {
+                                Some(Ok(request)) => request,
+                                _ => break,
+                            },
+                            _ = closed.recv() => break,
+                        };
+
+                        let status = *status.lock().unwrap();
+                        let reply = TacacsBody::AccountingReply(AccountingReply { status, server_msg: "".into(), data: "".into() });
+
+                        let header = request.header();
+                        let packet = Packet::from_body(&reply, header.seq_no + 1, header.session_id).unwrap();
+                        let mut reply_header = packet.header().clone();
+                        reply_header.flags.set(TacacsFlags::TAC_PLUS_SINGLE_CONNECT_FLAG, single_connect);
+
+                        let packet = Packet::new(reply_header, packet.body().to_vec()).unwrap();
+                        if framed.send(packet).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        (address, connections)
+    }
+
+    fn generate_watchdog() -> AccountingRequest {
+        AccountingRequest {
+            flags: TacacsAccountingFlags::WATCHDOG,
+            authen_method: TacacsAuthenticationMethod::TacPlusAuthenMethodNone,
+            priv_lvl: 0,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
+            user: "healthcheck".into(),
+            port: "".into(),
+            rem_address: "".into(),
+            args: vec![Argument::mandatory("service", "shell")].into(),
+        }
+    }
+
+    fn generate_pool(address: String, size: usize) -> ConnectionPool {
+        ConnectionPool::new(ServerConfig::new(address).with_timeout(Duration::from_secs(2)), size)
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(100))
+            .with_health_check_interval(Duration::from_millis(20))
+            .with_health_check(generate_watchdog())
+    }
+
+    async fn wait_for_healthy(pool: &ConnectionPool, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while pool.healthy_connection_count().await != count {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+    }
+
+    #[test]
+    fn test_backoff() {
+        let pool = ConnectionPool::new(ServerConfig::new("localhost"), 1)
+            .with_backoff(Duration::from_millis(100), Duration::from_secs(1));
+
+        for (failures, expected) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (30, 1000)] {
+            let delay = pool.maintenance.backoff(failures);
+            let expected = Duration::from_millis(expected);
+
+            assert!(delay >= expected / 2 && delay <= expected, "{:?} is outside the jitter for {:?}", delay, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_hands_out_sessions() -> anyhow::Result<()> {
+        let (address, _connections) = spawn_server(TacacsAccountingStatus::TacPlusAcctStatusSuccess).await;
+
+        let pool = generate_pool(address, 3);
+        assert!(pool.create_session().await.is_err());
+
+        pool.start();
+        wait_for_healthy(&pool, 3).await;
+
+        let reply = pool.create_session().await?.send_accounting_request(generate_watchdog()).await?;
+        assert_eq!(reply.status, TacacsAccountingStatus::TacPlusAcctStatusSuccess);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pool_reconnects() -> anyhow::Result<()> {
+        let (address, mut connections) = spawn_server(TacacsAccountingStatus::TacPlusAcctStatusSuccess).await;
+
+        let pool = generate_pool(address, 1);
+        pool.start();
+        wait_for_healthy(&pool, 1).await;
+
+        // Closing the connection server side leads to a new one.
+        drop(connections.recv().await);
+        let _reconnected = tokio::time::timeout(Duration::from_secs(5), connections.recv()).await?;
+
+        wait_for_healthy(&pool, 1).await;
+        assert!(pool.create_session().await.is_ok());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failing_health_check() -> anyhow::Result<()> {
+        let (address, mut connections) = spawn_server(TacacsAccountingStatus::TacPlusAcctStatusError).await;
+
+        let pool = generate_pool(address, 1);
+        pool.start();
+
+        // The pool keeps retrying, but never hands out the unhealthy connections.
+        for _ in 0..2 {
+            tokio::time::timeout(Duration::from_secs(5), connections.recv()).await?;
+        }
+
+        assert!(pool.create_session().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_connection_is_closed() -> anyhow::Result<()> {
+        let status = Arc::new(std::sync::Mutex::new(TacacsAccountingStatus::TacPlusAcctStatusSuccess));
+        let (address, _connections) = spawn_switchable_server(Arc::clone(&status), true).await;
+
+        let pool = generate_pool(address, 1);
+        pool.start();
+        wait_for_healthy(&pool, 1).await;
+        let connection = pool.slots[0].get().unwrap();
+
+        // Failing the health check closes the connection rather than just dropping it.
+        *status.lock().unwrap() = TacacsAccountingStatus::TacPlusAcctStatusError;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while connection.state() != ConnectionState::Closed {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await?;
+
+        Ok(())
+    }
+
+    async fn wait_for_closed(connection: &Connection) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while connection.state() != ConnectionState::Closed {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_close_closes_connections() -> anyhow::Result<()> {
+        let (address, _connections) = spawn_server(TacacsAccountingStatus::TacPlusAcctStatusSuccess).await;
+
+        let pool = generate_pool(address, 2);
+        pool.start();
+        wait_for_healthy(&pool, 2).await;
+        let connections: Vec<_> = pool.slots.iter().filter_map(|slot| slot.get()).collect();
+
+        pool.close().await;
+
+        for connection in &connections {
+            assert_eq!(connection.state(), ConnectionState::Closed);
+        }
+
+        // Nothing reopens them.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(pool.healthy_connection_count().await, 0);
+        assert!(pool.create_session().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drop_closes_connections() -> anyhow::Result<()> {
+        let (address, _connections) = spawn_server(TacacsAccountingStatus::TacPlusAcctStatusSuccess).await;
+
+        let pool = generate_pool(address, 1);
+        pool.start();
+        wait_for_healthy(&pool, 1).await;
+        let connection = pool.slots[0].get().unwrap();
+
+        drop(pool);
+        wait_for_closed(&connection).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refused_single_connect_is_replaced() -> anyhow::Result<()> {
+        let status = Arc::new(std::sync::Mutex::new(TacacsAccountingStatus::TacPlusAcctStatusSuccess));
+        let (address, mut connections) = spawn_switchable_server(status, false).await;
+
+        // Without a health check the refusal only shows once a session is handed out.
+        let pool = ConnectionPool::new(ServerConfig::new(address).with_timeout(Duration::from_secs(2)), 1)
+            .with_backoff(Duration::from_millis(10), Duration::from_millis(100));
+        pool.start();
+        wait_for_healthy(&pool, 1).await;
+        let connection = pool.slots[0].get().unwrap();
+
+        let reply = pool.create_session().await?.send_accounting_request(generate_watchdog()).await?;
+        assert_eq!(reply.status, TacacsAccountingStatus::TacPlusAcctStatusSuccess);
+
+        wait_for_closed(&connection).await;
+        for _ in 0..2 {
+            tokio::time::timeout(Duration::from_secs(5), connections.recv()).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::sessions::authentication_session::{AuthenticationPrompt, AuthenticationResponse};
+
+/// Answers the GETUSER, GETPASS and GETDATA prompts of an ASCII login.
+#[async_trait]
+pub trait Prompter : Send + Sync {
+    async fn prompt(&self, prompt: &AuthenticationPrompt) -> anyhow::Result<AuthenticationResponse>;
+}
+
+#[async_trait]
+impl<F> Prompter for F
+    where F: Fn(&AuthenticationPrompt) -> AuthenticationResponse + Send + Sync
+{
+    async fn prompt(&self, prompt: &AuthenticationPrompt) -> anyhow::Result<AuthenticationResponse> {
+        Ok(self(prompt))
+    }
+}
+
+
+/// Prompts on the controlling terminal, hiding the input when the server sets NOECHO.
+#[derive(Debug, Default)]
+pub struct TerminalPrompter;
+
+impl TerminalPrompter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn read_line(prompt: &AuthenticationPrompt) -> anyhow::Result<Option<String>> {
+        if prompt.no_echo {
+            return Ok(Some(rpassword::prompt_password(&prompt.server_msg)?));
+        }
+
+        let mut stdout = std::io::stdout();
+        stdout.write_all(prompt.server_msg.as_bytes())?;
+        stdout.flush()?;
+
+        let mut line = String::new();
+        if std::io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+}
+
+#[async_trait]
+impl Prompter for TerminalPrompter {
+    async fn prompt(&self, prompt: &AuthenticationPrompt) -> anyhow::Result<AuthenticationResponse> {
+        let prompt = prompt.clone();
+        let line = tokio::task::spawn_blocking(move || Self::read_line(&prompt)).await??;
+
+        Ok(match line {
+            Some(line) => AuthenticationResponse::Continue(line),
+            None => AuthenticationResponse::Abort("End of input on terminal".to_string()),
+        })
+    }
+}
+
+
+/// Replays a fixed list of responses and records the prompts it was given.
+#[derive(Debug, Default)]
+pub struct ScriptedPrompter {
+    responses: Mutex<VecDeque<AuthenticationResponse>>,
+    prompts: Mutex<Vec<AuthenticationPrompt>>,
+}
+
+impl ScriptedPrompter {
+    pub fn new(responses: impl IntoIterator<Item = AuthenticationResponse>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into_iter().collect()),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_answers<S: Into<String>>(answers: impl IntoIterator<Item = S>) -> Self {
+        Self::new(answers.into_iter().map(|answer| AuthenticationResponse::Continue(answer.into())))
+    }
+
+    pub fn prompts(&self) -> Vec<AuthenticationPrompt> {
+        self.prompts.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Prompter for ScriptedPrompter {
+    async fn prompt(&self, prompt: &AuthenticationPrompt) -> anyhow::Result<AuthenticationResponse> {
+        self.prompts.lock().unwrap().push(prompt.clone());
+
+        self.responses.lock().unwrap().pop_front()
+            .ok_or_else(|| anyhow::Error::msg("Scripted prompter has no responses left"))
+    }
+}
+
+
+/// A prompt forwarded to a front-end, answered through `respond`.
+#[derive(Debug)]
+pub struct PromptRequest {
+    pub prompt: AuthenticationPrompt,
+    responder: oneshot::Sender<AuthenticationResponse>,
+}
+
+impl PromptRequest {
+    pub fn respond(self, response: AuthenticationResponse) -> anyhow::Result<()> {
+        self.responder.send(response)
+            .map_err(|_| anyhow::Error::msg("Authentication is no longer waiting for a response"))
+    }
+}
+
+/// Forwards each prompt over a channel, for GUIs and web front-ends that
+/// collect the answer outside of the authentication task.
+#[derive(Debug, Clone)]
+pub struct ChannelPrompter {
+    sender: mpsc::Sender<PromptRequest>,
+}
+
+impl ChannelPrompter {
+    pub fn new(buffer: usize) -> (Self, mpsc::Receiver<PromptRequest>) {
+        let (sender, receiver) = mpsc::channel(buffer);
+        (Self { sender }, receiver)
+    }
+}
+
+#[async_trait]
+impl Prompter for ChannelPrompter {
+    async fn prompt(&self, prompt: &AuthenticationPrompt) -> anyhow::Result<AuthenticationResponse> {
+        let (responder, response) = oneshot::channel();
+
+        self.sender.send(PromptRequest { prompt: prompt.clone(), responder }).await
+            .map_err(|_| anyhow::Error::msg("Prompt receiver has been closed"))?;
+
+        response.await
+            .map_err(|_| anyhow::Error::msg("Prompt was dropped without a response"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tacacsrs_messages::enumerations::TacacsAuthenticationStatus;
+
+    fn generate_prompt(no_echo: bool) -> AuthenticationPrompt {
+        AuthenticationPrompt {
+            status: TacacsAuthenticationStatus::TacPlusAuthenStatusGetpass,
+            server_msg: "Password: ".to_string(),
+            data: "".into(),
+            no_echo,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scripted_prompter() {
+        let prompter = ScriptedPrompter::with_answers(["admin"]);
+
+        let response = prompter.prompt(&generate_prompt(true)).await.unwrap();
+        assert!(matches!(response, AuthenticationResponse::Continue(answer) if answer == "admin"));
+
+        assert!(prompter.prompt(&generate_prompt(true)).await.is_err());
+        assert_eq!(prompter.prompts().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_channel_prompter() {
+        let (prompter, mut receiver) = ChannelPrompter::new(1);
+
+        let front_end = tokio::spawn(async move {
+            let request = receiver.recv().await.unwrap();
+            assert!(request.prompt.no_echo);
+            request.respond(AuthenticationResponse::Continue("secret".to_string())).unwrap();
+        });
+
+        let response = prompter.prompt(&generate_prompt(true)).await.unwrap();
+        assert!(matches!(response, AuthenticationResponse::Continue(answer) if answer == "secret"));
+
+        front_end.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_channel_prompter_dropped_request() {
+        let (prompter, mut receiver) = ChannelPrompter::new(1);
+
+        let front_end = tokio::spawn(async move {
+            drop(receiver.recv().await.unwrap());
+        });
+
+        assert!(prompter.prompt(&generate_prompt(false)).await.is_err());
+
+        front_end.await.unwrap();
+    }
+}
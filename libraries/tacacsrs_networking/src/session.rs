@@ -1,6 +1,18 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::PoisonError;
+use std::time::Duration;
+use tacacsrs_messages::authentication::continuation::AuthenticationContinue;
+use tacacsrs_messages::body::TacacsBody;
+use tacacsrs_messages::enumerations::{TacacsFlags, TacacsMajorVersion, TacacsMinorVersion, TacacsType};
+use tacacsrs_messages::header::Header;
+use tacacsrs_messages::packet::{Packet, PacketTrait};
+use tacacsrs_messages::traits::TacacsBodyTrait;
 use tokio::sync::RwLock;
 
 use crate::duplex_channel::DuplexChannel;
+use crate::error::SessionError;
+use crate::events::ConnectionEvent;
+use crate::session_manager::SessionRegistration;
 
 
 pub struct Session
@@ -9,7 +21,15 @@ pub struct Session
     pub duplex_channel: DuplexChannel,
 
     pub current_sequence_number: RwLock<u8>,
-    pub session_complete: RwLock<bool>
+    pub minor_version: RwLock<TacacsMinorVersion>,
+    pub session_complete: RwLock<bool>,
+    pub reply_timeout: RwLock<Option<Duration>>,
+
+    session_type: RwLock<Option<TacacsType>>,
+    expected_reply: RwLock<Option<(u8, TacacsType)>>,
+    cancelled: AtomicBool,
+    request_single_connect: AtomicBool,
+    registration: std::sync::Mutex<Option<SessionRegistration>>
 }
 
 
@@ -22,10 +42,42 @@ impl Session
             session_id,
             duplex_channel,
             current_sequence_number: 1_u8.into(),
-            session_complete: false.into()
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault.into(),
+            session_complete: false.into(),
+            reply_timeout: None.into(),
+            session_type: None.into(),
+            expected_reply: None.into(),
+            cancelled: false.into(),
+            request_single_connect: false.into(),
+            registration: None.into()
         }
     }
 
+    /// How long `receive` waits for a reply. `None` waits indefinitely.
+    pub fn with_reply_timeout(mut self, reply_timeout: Option<Duration>) -> Self
+    {
+        *self.reply_timeout.get_mut() = reply_timeout;
+        self
+    }
+
+    pub async fn set_reply_timeout(&self, reply_timeout: Option<Duration>)
+    {
+        *self.reply_timeout.write().await = reply_timeout;
+    }
+
+    pub(crate) fn with_registration(self, registration: SessionRegistration) -> Self
+    {
+        *self.registration.lock().unwrap_or_else(PoisonError::into_inner) = Some(registration);
+        self
+    }
+
+    /// Sets the single-connect flag on the first packet this session sends.
+    pub(crate) fn with_single_connect_request(self, request_single_connect: bool) -> Self
+    {
+        self.request_single_connect.store(request_single_connect, Ordering::SeqCst);
+        self
+    }
+
     pub fn session_id(&self) -> u32
     {
         self.session_id
@@ -33,17 +85,169 @@ impl Session
 
     pub async fn next_sequence_number(&self) -> u8
     {
+        // Saturates rather than wraps, so an exhausted session stays exhausted.
         let mut sequence_number_lock = self.current_sequence_number.write().await;
         let sequence_number = *sequence_number_lock;
-        *sequence_number_lock = sequence_number.wrapping_add(2);
+        *sequence_number_lock = sequence_number.saturating_add(2);
 
         sequence_number
     }
 
+    /// Wraps the body in a packet carrying the next sequence number for this
+    /// session and queues it for sending on the connection. The next packet
+    /// received must be the reply to it.
+    pub async fn send(&self, tacacs_type: TacacsType, minor_version: TacacsMinorVersion, body: &(dyn TacacsBodyTrait + Send + Sync)) -> anyhow::Result<u8>
+    {
+        let sequence_number = self.next_sequence_number().await;
+
+        // RFC 8907 section 4.1: the sequence number must not wrap. A request numbered
+        // 255 could never be answered, so 253 is the last one sent.
+        if sequence_number == u8::MAX
+        {
+            return Err(SessionError::SequenceNumbersExhausted.into());
+        }
+
+        *self.session_type.write().await = Some(tacacs_type);
+        *self.expected_reply.write().await = Some((sequence_number + 1, tacacs_type));
+
+        let mut flags = TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG;
+        if self.request_single_connect.swap(false, Ordering::SeqCst)
+        {
+            flags |= TacacsFlags::TAC_PLUS_SINGLE_CONNECT_FLAG;
+        }
+
+        let data = body.try_to_bytes().map_err(SessionError::InvalidRequest)?;
+
+        let packet = Packet::new(Header {
+            major_version : TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version,
+            tacacs_type,
+            seq_no : sequence_number,
+            flags,
+            session_id : self.session_id(),
+            length : data.len() as u32
+        }, data).map_err(SessionError::InvalidRequest)?;
+
+        self.duplex_channel.sender.send(packet).await?;
+
+        Ok(sequence_number)
+    }
+
+    /// Waits for the next packet routed to this session by the connection. If the
+    /// reply timeout passes first, the session is cancelled.
+    pub async fn receive(&self) -> anyhow::Result<Packet>
+    {
+        let reply_timeout = *self.reply_timeout.read().await;
+
+        // Setup a reader lock to receive the response, it needs to be mutable so that we can call recv on it
+        // therefore we need to use write() instead of read()
+        let mut reader_lock = self.duplex_channel.receiver.write().await;
+
+        let response = match reply_timeout {
+            Some(duration) => match tokio::time::timeout(duration, reader_lock.recv()).await {
+                Ok(response) => response,
+                Err(_) => {
+                    drop(reader_lock);
+
+                    log::warn!(
+                        target: "tacacsrs_networking::session::receive",
+                        "No reply received within {:?} for session {}, cancelling",
+                        duration, self.session_id
+                    );
+
+                    self.cancel().await?;
+                    return Err(SessionError::Timeout(duration).into());
+                }
+            },
+            None => reader_lock.recv().await,
+        };
+
+        match response {
+            Some(response) => self.validate_reply(response?).await,
+            None if self.cancelled.load(Ordering::SeqCst) => Err(SessionError::Cancelled.into()),
+            None => Err(SessionError::ConnectionClosed("no reply was received".to_string()).into())
+        }
+    }
+
+    /// Checks that the packet answers the last request sent. A packet that does not
+    /// is rejected and the session is closed, so a stale or forged reply cannot be
+    /// taken as the answer to a later request.
+    async fn validate_reply(&self, packet: Packet) -> anyhow::Result<Packet>
+    {
+        let header = packet.header();
+        let expected_reply = self.expected_reply.write().await.take();
+
+        let error = match expected_reply {
+            None => SessionError::UnsolicitedPacket(header.seq_no),
+            Some((expected, _)) if header.seq_no != expected => SessionError::UnexpectedSequenceNumber { expected, received: header.seq_no },
+            Some((_, expected)) if header.tacacs_type != expected => SessionError::UnexpectedPacketType { expected, received: header.tacacs_type },
+            Some(_) => {
+                if let Some(registration) = self.registration.lock().unwrap_or_else(PoisonError::into_inner).as_ref()
+                {
+                    registration.record_single_connect(header);
+                }
+
+                return Ok(packet);
+            },
+        };
+
+        log::warn!(
+            target: "tacacsrs_networking::session::receive",
+            "Rejected packet for session {}: {}",
+            self.session_id, error
+        );
+
+        if let Some(registration) = self.registration.lock().unwrap_or_else(PoisonError::into_inner).as_ref()
+        {
+            registration.emit(ConnectionEvent::ProtocolError(error.to_string()));
+        }
+
+        self.complete().await;
+
+        Err(error.into())
+    }
+
+    /// Abandons the exchange and deregisters the session. An authentication session
+    /// is sent an abort first; authorization and accounting have no abort, so those
+    /// are only closed. A concurrent `receive` fails with `SessionError::Cancelled`.
+    pub async fn cancel(&self) -> anyhow::Result<()>
+    {
+        // Only the flag is checked: is_complete would wait on a concurrent receive.
+        if *self.session_complete.read().await
+        {
+            return Ok(());
+        }
+
+        self.cancelled.store(true, Ordering::SeqCst);
+
+        let session_type = *self.session_type.read().await;
+        let result = match session_type {
+            Some(TacacsType::TacPlusAuthentication) => {
+                let minor_version = *self.minor_version.read().await;
+                self.send(TacacsType::TacPlusAuthentication, minor_version, &AuthenticationContinue::abort("Cancelled by client")).await.map(|_| ())
+            },
+            _ => Ok(()),
+        };
+
+        self.complete().await;
+
+        result
+    }
+
+    /// Marks the session complete and deregisters it from its connection.
     pub async fn complete(&self)
     {
         let mut session_complete_lock = self.session_complete.write().await;
         *session_complete_lock = true;
+
+        self.close();
+    }
+
+    /// Deregisters the session from its connection, which then drops any further
+    /// replies for it. Dropping the session does the same.
+    pub fn close(&self)
+    {
+        self.registration.lock().unwrap_or_else(PoisonError::into_inner).take();
     }
 
     pub async fn is_complete(&self) -> bool
@@ -64,11 +268,18 @@ impl Session
 }
 
 
+/// The error returned when a session receives a body other than the one the exchange calls for.
+pub(crate) fn unexpected_body(expected: &str, body: &TacacsBody) -> anyhow::Error {
+    let sender = if body.is_from_client() { "client" } else { "server" };
+    SessionError::UnexpectedBody(format!("Expected {}, received a {} body from the {}", expected, body.tacacs_type(), sender)).into()
+}
+
+
 #[cfg(test)]
 mod tests
 {
     use super::*;
-    use crate::duplex_channel::DuplexChannel;
+    use crate::duplex_channel::{DuplexChannel, SessionMessage};
     use tacacsrs_messages::packet::Packet;
     use tokio::sync::mpsc;
 
@@ -76,7 +287,7 @@ mod tests
     async fn test_session()
     {
         let (network_sender, _network_receiver) = mpsc::channel::<Packet>(32);
-        let (_client_sender, client_receiver) = mpsc::channel::<Packet>(32);
+        let (_client_sender, client_receiver) = mpsc::channel::<SessionMessage>(32);
         let duplex_channel = DuplexChannel::new(client_receiver, network_sender);
 
         let session = Session::new(1, duplex_channel);
@@ -93,7 +304,7 @@ mod tests
     async fn test_sequence_number()
     {
         let (network_sender, _network_receiver) = mpsc::channel::<Packet>(32);
-        let (_client_sender, client_receiver) = mpsc::channel::<Packet>(32);
+        let (_client_sender, client_receiver) = mpsc::channel::<SessionMessage>(32);
         let duplex_channel = DuplexChannel::new(client_receiver, network_sender);
 
         let session = Session::new(1, duplex_channel);
@@ -109,7 +320,7 @@ mod tests
     async fn test_is_complete_network_closed()
     {
         let (network_sender, _network_receiver) = mpsc::channel::<Packet>(32);
-        let (_client_sender, client_receiver) = mpsc::channel::<Packet>(32);
+        let (_client_sender, client_receiver) = mpsc::channel::<SessionMessage>(32);
         let duplex_channel = DuplexChannel::new(client_receiver, network_sender);
 
         let session = Session::new(1, duplex_channel);
@@ -128,4 +339,24 @@ mod tests
         // the client receiver is closed
         assert!(session.duplex_channel.receiver_closed().await);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_sequence_numbers_exhausted()
+    {
+        let (network_sender, _network_receiver) = mpsc::channel::<Packet>(256);
+        let (_client_sender, client_receiver) = mpsc::channel::<SessionMessage>(32);
+        let duplex_channel = DuplexChannel::new(client_receiver, network_sender);
+
+        let session = Session::new(1, duplex_channel);
+        let body = AuthenticationContinue::abort("");
+
+        for _ in 0..127
+        {
+            session.send(TacacsType::TacPlusAuthentication, TacacsMinorVersion::TacacsPlusMinorVerDefault, &body).await.unwrap();
+        }
+
+        let error = session.send(TacacsType::TacPlusAuthentication, TacacsMinorVersion::TacacsPlusMinorVerDefault, &body).await.unwrap_err();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::SequenceNumbersExhausted));
+        assert_eq!(*session.current_sequence_number.read().await, u8::MAX);
+    }
+}
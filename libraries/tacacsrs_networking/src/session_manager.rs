@@ -1,18 +1,158 @@
 use std::collections::HashMap;
+use std::sync::{Arc, MutexGuard, PoisonError, Weak};
+use std::time::Duration;
+use tacacsrs_messages::enumerations::TacacsFlags;
+use tacacsrs_messages::header::Header;
 use tacacsrs_messages::packet::{Packet, PacketTrait};
 
-use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc, watch, Mutex, Notify, RwLock};
 
-use crate::duplex_channel::DuplexChannel;
+use crate::duplex_channel::{DuplexChannel, SessionMessage};
+use crate::error::SessionError;
+use crate::events::{ConnectionEvent, ConnectionState, EVENT_CHANNEL_CAPACITY};
 use crate::session::Session;
 
+type SessionChannels = HashMap<u32, mpsc::Sender<SessionMessage>>;
+
+// A std mutex rather than a tokio one, so that sessions can deregister from Drop.
+// It is never held across an await.
+fn lock_channels(duplex_channels: &std::sync::Mutex<SessionChannels>) -> MutexGuard<'_, SessionChannels>
+{
+    duplex_channels.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Held by a session for as long as it is registered with its connection.
+/// Dropping it removes the session's channel.
+#[derive(Debug)]
+pub(crate) struct SessionRegistration {
+    session_id: u32,
+    duplex_channels: Weak<std::sync::Mutex<SessionChannels>>,
+    drained: Arc<Notify>,
+    single_connect: Arc<watch::Sender<SingleConnectState>>,
+    state: Arc<std::sync::Mutex<ConnectionState>>,
+    events: broadcast::Sender<ConnectionEvent>,
+}
+
+impl SessionRegistration
+{
+    /// Publishes an event on the session's connection.
+    pub(crate) fn emit(&self, event: ConnectionEvent)
+    {
+        let _ = self.events.send(event);
+    }
+
+    /// Records the server's answer if this session requested single-connect. The
+    /// session calls this with its first reply once the reply has been validated,
+    /// so a stale or forged packet cannot settle the negotiation.
+    pub(crate) fn record_single_connect(&self, header: &Header)
+    {
+        let agreed = header.flags.contains(TacacsFlags::TAC_PLUS_SINGLE_CONNECT_FLAG);
+
+        let answered = self.single_connect.send_if_modified(|single_connect| {
+            if *single_connect != (SingleConnectState::Requested { session_id: self.session_id })
+            {
+                return false;
+            }
+
+            *single_connect = if agreed { SingleConnectState::Agreed } else { SingleConnectState::Refused };
+            true
+        });
+
+        if !answered
+        {
+            return;
+        }
+
+        log::info!(
+            target: "tacacsrs_networking::session_manager::single_connect",
+            "Server {} single-connect mode",
+            if agreed { "agreed to" } else { "refused" }
+        );
+
+        self.emit(ConnectionEvent::SingleConnectNegotiated { agreed });
+
+        if agreed
+        {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            if *state == ConnectionState::Established
+            {
+                *state = ConnectionState::SingleConnect;
+            }
+        }
+    }
+}
+
+impl Drop for SessionRegistration
+{
+    fn drop(&mut self)
+    {
+        // A session that asked for single-connect and went away unanswered hands
+        // the request on to the next session created.
+        self.single_connect.send_if_modified(|state| {
+            let unanswered = *state == SingleConnectState::Requested { session_id: self.session_id };
+            if unanswered
+            {
+                *state = SingleConnectState::NotRequested;
+            }
+            unanswered
+        });
+
+        if let Some(duplex_channels) = self.duplex_channels.upgrade()
+        {
+            let (removed, drained) = {
+                let mut channels = lock_channels(&duplex_channels);
+                (channels.remove(&self.session_id).is_some(), channels.is_empty())
+            };
+
+            if removed
+            {
+                self.emit(ConnectionEvent::SessionClosed(self.session_id));
+
+                if drained
+                {
+                    self.drained.notify_waiters();
+                }
+
+                log::debug!(
+                    target: "tacacsrs_networking::session_manager::deregister",
+                    "Deregistered session id {}",
+                    self.session_id
+                );
+            }
+        }
+    }
+}
+
+/// Where a connection is in the RFC 8907 section 4.3 single-connect negotiation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SingleConnectState {
+    /// No session has been created yet.
+    NotRequested,
+    /// The first session asked for single-connect and its reply has not arrived.
+    /// Creating another session waits for the answer.
+    Requested { session_id: u32 },
+    /// The server echoed the flag, so any number of sessions may share the connection.
+    Agreed,
+    /// The server did not echo the flag. The connection carries only the first session.
+    Refused,
+}
+
 #[derive(Debug)]
 pub struct SessionManager {
-    pub(crate) duplex_channels: RwLock<HashMap<u32, mpsc::Sender<Packet>>>,
+    pub(crate) duplex_channels: Arc<std::sync::Mutex<SessionChannels>>,
+    drained: Arc<Notify>,
     pub(crate) sender: tokio::sync::mpsc::Sender<Packet>,
     pub(crate) receiver: Mutex<Option<tokio::sync::mpsc::Receiver<Packet>>>,
     
-    can_accept_new_sessions: RwLock<bool>
+    pub(crate) reply_timeout: Option<Duration>,
+
+    can_accept_new_sessions: RwLock<bool>,
+    // Held while a session is created, so that only one caller can claim the
+    // single-connect request.
+    creating_session: Mutex<()>,
+    single_connect: Arc<watch::Sender<SingleConnectState>>,
+    state: Arc<std::sync::Mutex<ConnectionState>>,
+    events: broadcast::Sender<ConnectionEvent>
 }
 
 impl SessionManager
@@ -23,25 +163,77 @@ impl SessionManager
 
         Self
         {
-            duplex_channels: HashMap::new().into(),
+            duplex_channels: Arc::new(HashMap::new().into()),
+            drained: Arc::new(Notify::new()),
             sender,
             receiver: Some(receiver).into(),
-            can_accept_new_sessions: true.into()
+            reply_timeout: None,
+            can_accept_new_sessions: true.into(),
+            creating_session: Mutex::new(()),
+            single_connect: Arc::new(watch::Sender::new(SingleConnectState::NotRequested)),
+            state: Arc::new(ConnectionState::Connecting.into()),
+            events: broadcast::Sender::new(EVENT_CHANNEL_CAPACITY)
+        }
+    }
+
+    pub fn state(&self) -> ConnectionState
+    {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Moves to `state`, unless the connection has already closed or failed.
+    pub(crate) fn set_state(&self, state: ConnectionState)
+    {
+        let mut current = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if !current.is_terminal()
+        {
+            *current = state;
         }
     }
 
+    /// Events published from now on. A receiver that falls more than
+    /// `EVENT_CHANNEL_CAPACITY` events behind misses the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<ConnectionEvent>
+    {
+        self.events.subscribe()
+    }
+
+    pub(crate) fn emit(&self, event: ConnectionEvent)
+    {
+        log::debug!(
+            target: "tacacsrs_networking::session_manager::emit",
+            "Connection event: {:?}",
+            event
+        );
+
+        // Nobody listening is not an error.
+        let _ = self.events.send(event);
+    }
+
+    /// The packets sessions queue for sending. Only one task can write them, so
+    /// this succeeds once.
+    pub(crate) async fn take_receiver(&self) -> anyhow::Result<mpsc::Receiver<Packet>>
+    {
+        self.receiver.lock().await.take()
+            .ok_or_else(|| anyhow::Error::msg("Connection is already running"))
+    }
+
     pub(crate) async fn disable_new_sessions(&self)
     {
         let mut can_accept_lock = self.can_accept_new_sessions.write().await;
         *can_accept_lock = false;
+        drop(can_accept_lock);
+
+        // Wakes anyone in create_session waiting on the single-connect answer.
+        self.single_connect.send_modify(|_| {});
     }
 
-    pub(crate) async fn create_channel(&self) -> anyhow::Result<(DuplexChannel, u32)>
+    pub(crate) async fn create_channel(&self) -> anyhow::Result<(DuplexChannel, SessionRegistration)>
     {
         // create some channel where the send side connects to the internal MPSC receiver
         // aka clone the sender and pass it to the DuplexChannel. Then create a new mpsc
         // and associate that with the session id here inside the connection.
-        let (session_sender, session_receiver) = mpsc::channel::<Packet>(32);
+        let (session_sender, session_receiver) = mpsc::channel::<SessionMessage>(32);
 
         let duplex_channel = DuplexChannel::new(session_receiver, self.sender.clone() );
 
@@ -50,7 +242,7 @@ impl SessionManager
 
         // get lock on duplex_channels and then insert the new session id
         {
-            let mut duplex_channels = self.duplex_channels.write().await;
+            let mut duplex_channels = lock_channels(&self.duplex_channels);
 
             while duplex_channels.contains_key(&session_id)
             {
@@ -60,24 +252,94 @@ impl SessionManager
             duplex_channels.insert(session_id, session_sender);
         }
 
-        Ok((duplex_channel, session_id))
+        let registration = SessionRegistration {
+            session_id,
+            duplex_channels: Arc::downgrade(&self.duplex_channels),
+            drained: Arc::clone(&self.drained),
+            single_connect: Arc::clone(&self.single_connect),
+            state: Arc::clone(&self.state),
+            events: self.events.clone(),
+        };
+
+        Ok((duplex_channel, registration))
+    }
+
+    /// The ids of the sessions still registered with this connection.
+    pub fn active_session_ids(&self) -> Vec<u32>
+    {
+        lock_channels(&self.duplex_channels).keys().copied().collect()
+    }
+
+    pub fn active_session_count(&self) -> usize
+    {
+        lock_channels(&self.duplex_channels).len()
+    }
+
+    /// Waits until no sessions are registered with this connection.
+    pub(crate) async fn sessions_drained(&self)
+    {
+        loop
+        {
+            // Registered before checking the count, so a session deregistering
+            // in between still wakes us.
+            let notified = self.drained.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.active_session_count() == 0
+            {
+                return;
+            }
+
+            notified.await;
+        }
     }
 
 
+    pub fn single_connect(&self) -> SingleConnectState
+    {
+        *self.single_connect.borrow()
+    }
+
+    /// False once new sessions are disabled, or once the server has refused
+    /// single-connect, as the connection then carries only its first session.
     pub async fn can_create_sessions(&self) -> bool
     {
         let can_accept_lock = self.can_accept_new_sessions.read().await;
-        *can_accept_lock
+        *can_accept_lock && self.single_connect() != SingleConnectState::Refused
     }
 
+    /// The first session on a connection asks the server for single-connect mode.
+    /// Until the server has answered, creating another session waits, as the
+    /// connection may turn out to carry only the first.
     pub async fn create_session(&self) -> anyhow::Result<Session>
     {
-        if !self.can_create_sessions().await
+        let _creating_session = self.creating_session.lock().await;
+        let mut single_connect = self.single_connect.subscribe();
+
+        loop
         {
-            return Err(anyhow::Error::msg("Connection is not accepting new sessions"));
+            if *single_connect.borrow_and_update() == SingleConnectState::Refused
+            {
+                return Err(SessionError::SingleConnectRefused.into());
+            }
+
+            if !self.can_create_sessions().await
+            {
+                return Err(anyhow::Error::msg("Connection is not accepting new sessions"));
+            }
+
+            if !matches!(*single_connect.borrow(), SingleConnectState::Requested { .. })
+            {
+                break;
+            }
+
+            // The manager holds a sender, so this cannot fail.
+            let _ = single_connect.changed().await;
         }
 
-        let (duplex_channel, session_id) = self.create_channel().await?;
+        let (duplex_channel, registration) = self.create_channel().await?;
+        let session_id = registration.session_id;
 
         log::info!(
             target: "tacacsrs_networking::connection::create_session",
@@ -85,17 +347,58 @@ impl SessionManager
             session_id
         );
 
-        Ok(Session::new(session_id, duplex_channel))
+        let request_single_connect = self.single_connect.send_if_modified(|single_connect| {
+            let first_session = *single_connect == SingleConnectState::NotRequested;
+
+            if first_session
+            {
+                *single_connect = SingleConnectState::Requested { session_id };
+            }
+
+            first_session
+        });
+
+        self.emit(ConnectionEvent::SessionOpened(session_id));
+
+        Ok(Session::new(session_id, duplex_channel)
+            .with_registration(registration)
+            .with_reply_timeout(self.reply_timeout)
+            .with_single_connect_request(request_single_connect))
+    }
+
+    /// Stops new sessions and hands the error to every open session, which
+    /// then sees it in place of a reply.
+    pub(crate) async fn fail_sessions(&self, error: SessionError)
+    {
+        self.disable_new_sessions().await;
+
+        let channels = std::mem::take(&mut *lock_channels(&self.duplex_channels));
+
+        for (session_id, channel) in channels
+        {
+            log::warn!(
+                target: "tacacsrs_networking::session_manager::fail_sessions",
+                "Failing session id {}: {}",
+                session_id, error
+            );
+
+            // If the channel is full the session still sees it close, just without the reason.
+            let _ = channel.try_send(Err(error.clone()));
+
+            self.emit(ConnectionEvent::SessionClosed(session_id));
+        }
+
+        self.drained.notify_waiters();
     }
 
     pub async fn send_message_to_session(&self, packet: Packet) -> anyhow::Result<()>
     {
-        // Get a read lock on the duplex_channels dictionary and 
-        // find the appropriate channel to forward the packet to.
-        let duplex_channels = self.duplex_channels.read().await;
+        // Find the appropriate channel to forward the packet to. The sender is cloned
+        // so that the lock is released before waiting on the channel.
         let session_id = packet.header().session_id;
+        let channel = lock_channels(&self.duplex_channels).get(&session_id).cloned();
 
-        match duplex_channels.get(&session_id) {
+        match channel {
             Some(channel) => {
                 log::info!(
                     target: "tacacsrs_networking::session_manager::send_message_to_session",
@@ -103,14 +406,14 @@ impl SessionManager
                     session_id
                 );
 
-                match channel.send(packet).await
+                match channel.send(Ok(packet)).await
                 {
                     Ok(_) => Ok(()),
                     Err(e) => {
                         log::warn!(
                             target: "tacacsrs_networking::session_manager::send_message_to_session",
                             "Failed to send packet to client channel for session id: {} due to error: {}",
-                            session_id, e.to_string()
+                            session_id, e
                         );
 
                         Err(anyhow::Error::msg("Failed to send packet to client channel"))
@@ -118,8 +421,9 @@ impl SessionManager
                 }
             },
             
+            // Most likely a late reply for a session that has completed or been cancelled.
             None => {
-                Err(anyhow::Error::msg("No client channel found for session id"))
+                Err(SessionError::UnknownSession(session_id).into())
             }
         }
     }
@@ -130,15 +434,16 @@ impl SessionManager
 mod tests
 {
     use super::*;
+    use tacacsrs_messages::enumerations::{TacacsMinorVersion, TacacsType};
 
     #[tokio::test]
     async fn test_create_channel()
     {
         let session_manager = SessionManager::new();
 
-        let (_, session_id) = session_manager.create_channel().await.unwrap();
+        let (_, registration) = session_manager.create_channel().await.unwrap();
 
-        assert_ne!(session_id, 0);
+        assert_ne!(registration.session_id, 0);
     }
 
     #[tokio::test]
@@ -146,10 +451,10 @@ mod tests
     {
         let session_manager = SessionManager::new();
 
-        let (_, session_id) = session_manager.create_channel().await.unwrap();
-        let (_, session_id2) = session_manager.create_channel().await.unwrap();
+        let (_, registration) = session_manager.create_channel().await.unwrap();
+        let (_, registration2) = session_manager.create_channel().await.unwrap();
 
-        assert_ne!(session_id, session_id2);
+        assert_ne!(registration.session_id, registration2.session_id);
     }
 
     #[tokio::test]
@@ -190,4 +495,139 @@ mod tests
 
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_fail_sessions()
+    {
+        let session_manager = SessionManager::new();
+
+        let session = session_manager.create_session().await.unwrap();
+
+        session_manager.fail_sessions(SessionError::ProtocolViolation("bad header".to_string())).await;
+
+        let error = session.receive().await.unwrap_err();
+        assert_eq!(error.downcast_ref::<SessionError>(), Some(&SessionError::ProtocolViolation("bad header".to_string())));
+
+        assert!(!session_manager.can_create_sessions().await);
+        assert_eq!(session_manager.active_session_count(), 0);
+    }
+
+    fn generate_reply(session_id: u32, seq_no: u8) -> Packet
+    {
+        Packet::new(tacacsrs_messages::header::Header {
+            major_version: tacacsrs_messages::enumerations::TacacsMajorVersion::TacacsPlusMajor1,
+            minor_version: TacacsMinorVersion::TacacsPlusMinorVerDefault,
+            tacacs_type: TacacsType::TacPlusAccounting,
+            seq_no,
+            flags: TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG | TacacsFlags::TAC_PLUS_SINGLE_CONNECT_FLAG,
+            session_id,
+            length: 5,
+        }, vec![0, 0, 0, 0, 1]).unwrap()
+    }
+
+    async fn answer_single_connect(session_manager: &SessionManager, session: &Session, reply: Packet) -> anyhow::Result<Packet>
+    {
+        let body = tacacsrs_messages::authentication::continuation::AuthenticationContinue::abort("");
+        session.send(TacacsType::TacPlusAccounting, TacacsMinorVersion::TacacsPlusMinorVerDefault, &body).await.unwrap();
+        session_manager.send_message_to_session(reply).await.unwrap();
+        session.receive().await
+    }
+
+    #[tokio::test]
+    async fn test_sessions_deregister()
+    {
+        let session_manager = SessionManager::new();
+
+        let first = session_manager.create_session().await.unwrap();
+        answer_single_connect(&session_manager, &first, generate_reply(first.session_id(), 2)).await.unwrap();
+        let second = session_manager.create_session().await.unwrap();
+        let third = session_manager.create_session().await.unwrap();
+
+        assert_eq!(session_manager.active_session_count(), 3);
+
+        drop(first);
+        second.complete().await;
+        third.close();
+
+        assert_eq!(session_manager.active_session_count(), 0);
+        assert!(session_manager.active_session_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_first_session_requests_single_connect()
+    {
+        let session_manager = Arc::new(SessionManager::new());
+        let mut network_receiver = session_manager.receiver.lock().await.take().unwrap();
+
+        let first = session_manager.create_session().await.unwrap();
+        let second = {
+            let session_manager = Arc::clone(&session_manager);
+            tokio::spawn(async move { session_manager.create_session().await })
+        };
+
+        answer_single_connect(&session_manager, &first, generate_reply(first.session_id(), 2)).await.unwrap();
+        let second = second.await.unwrap().unwrap();
+
+        let body = tacacsrs_messages::authentication::continuation::AuthenticationContinue::abort("");
+        first.send(TacacsType::TacPlusAccounting, TacacsMinorVersion::TacacsPlusMinorVerDefault, &body).await.unwrap();
+        second.send(TacacsType::TacPlusAccounting, TacacsMinorVersion::TacacsPlusMinorVerDefault, &body).await.unwrap();
+
+        let flags: Vec<bool> = (0..3)
+            .map(|_| network_receiver.try_recv().unwrap().header().flags.contains(TacacsFlags::TAC_PLUS_SINGLE_CONNECT_FLAG))
+            .collect();
+
+        // Only the first packet written asks for single-connect.
+        assert_eq!(flags, vec![true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_reply_does_not_answer_single_connect()
+    {
+        let session_manager = SessionManager::new();
+        let _network_receiver = session_manager.take_receiver().await.unwrap();
+
+        let first = session_manager.create_session().await.unwrap();
+
+        let stale = generate_reply(first.session_id(), 4);
+
+        assert!(answer_single_connect(&session_manager, &first, stale).await.is_err());
+        assert_eq!(session_manager.single_connect(), SingleConnectState::NotRequested);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_single_connect_request_passes_on()
+    {
+        let session_manager = Arc::new(SessionManager::new());
+
+        let first = session_manager.create_session().await.unwrap();
+        let second = {
+            let session_manager = Arc::clone(&session_manager);
+            tokio::spawn(async move { session_manager.create_session().await })
+        };
+
+        tokio::task::yield_now().await;
+        assert!(!second.is_finished());
+
+        // The first session goes away without an answer, so the next one asks instead.
+        drop(first);
+        let second = second.await.unwrap().unwrap();
+        assert_eq!(session_manager.single_connect(), SingleConnectState::Requested { session_id: second.session_id() });
+    }
+
+    #[tokio::test]
+    async fn test_waiting_session_fails_when_sessions_disabled()
+    {
+        let session_manager = Arc::new(SessionManager::new());
+
+        let _first = session_manager.create_session().await.unwrap();
+        let second = {
+            let session_manager = Arc::clone(&session_manager);
+            tokio::spawn(async move { session_manager.create_session().await })
+        };
+
+        tokio::task::yield_now().await;
+        session_manager.disable_new_sessions().await;
+
+        assert!(second.await.unwrap().is_err());
+    }
+}
@@ -1,12 +1,10 @@
 use async_trait::async_trait;
 use log::info;
-use tacacsrs_messages::header::Header;
-use tacacsrs_messages::packet::{Packet, PacketTrait};
-use tacacsrs_messages::traits::TacacsBodyTrait;
 use tacacsrs_messages::accounting::{request::AccountingRequest, reply::AccountingReply};
-use tacacsrs_messages::enumerations::{TacacsMajorVersion, TacacsMinorVersion, TacacsType, TacacsFlags};
+use tacacsrs_messages::body::TacacsBody;
+use tacacsrs_messages::enumerations::{TacacsMinorVersion, TacacsType};
 
-use crate::session::Session;
+use crate::session::{unexpected_body, Session};
 
 #[async_trait]
 pub trait AccountingSessionTrait {
@@ -21,39 +19,19 @@ impl AccountingSessionTrait for Session {
             return Err(anyhow::Error::msg("Session is already complete"));
         }
 
-        let sequence_number = self.next_sequence_number().await;
-
-        let data = request.to_bytes();
-
-        let packet = Packet::new(Header {
-            major_version : TacacsMajorVersion::TacacsPlusMajor1,
-            minor_version : TacacsMinorVersion::TacacsPlusMinorVerDefault,
-            tacacs_type : TacacsType::TacPlusAccounting,
-            seq_no : sequence_number,
-            flags : TacacsFlags::TAC_PLUS_UNENCRYPTED_FLAG,
-            session_id : self.session_id(),
-            length : data.len() as u32
-        }, data)?;
+        let sequence_number = self.send(TacacsType::TacPlusAccounting, TacacsMinorVersion::TacacsPlusMinorVerDefault, &request).await?;
 
         info!(
             target: "tacacsrs_networking::sessions::accounting_session",
-            "Sending Accounting Request with sequence number {} for session {}",
+            "Sent Accounting Request with sequence number {} for session {}",
             sequence_number, self.session_id()
         );
-        
-        self.duplex_channel.sender.send(packet).await?;
 
-        // Setup a reader lock to receive the response, it needs to be mutable so that we can call recv on it
-        // therefore we need to use write() instead of read()
-        let mut reader_lock = self.duplex_channel.receiver.write().await;
-
-        let response = match reader_lock.recv().await {
-            Some(response) => response,
-            None => return Err(anyhow::Error::msg("Failed to receive response"))
+        let reply = match self.receive().await?.decode_body()? {
+            TacacsBody::AccountingReply(reply) => reply,
+            body => return Err(unexpected_body("an Accounting Reply", &body)),
         };
 
-        let reply = AccountingReply::from_bytes(response.body())?;
-
         self.complete().await;
 
         log::info!(
@@ -76,6 +54,7 @@ mod tests {
 
     use std::sync::Arc;
     use tacacsrs_messages::enumerations::*;
+    use tacacsrs_messages::arguments::argument::Argument;
 
     use crate::traits::SessionManagementTrait;
     use test_log::test;
@@ -99,21 +78,21 @@ mod tests {
             priv_lvl: 0,
             authen_type: TacacsAuthenticationType::TacPlusAuthenTypeNotSet,
             authen_service: TacacsAuthenticationService::TacPlusAuthenSvcNone,
-            user: "admin".to_string(),
-            port: "test".to_string(),
-            rem_address: "1.1.1.1".to_string(),
+            user: "admin".into(),
+            port: "test".into(),
+            rem_address: "1.1.1.1".into(),
             args: vec![
-                "service=shell".to_string(),
-                "task_id=123".to_string(),
-                "cmd=test".to_string()
-            ],
+                Argument::mandatory("service", "shell"),
+                Argument::mandatory("task_id", "123"),
+                Argument::mandatory("cmd", "test")
+            ].into(),
         };
     
         let accounting_reply = AccountingReply
         {
             status: TacacsAccountingStatus::TacPlusAcctStatusSuccess,
-            server_msg: "Test".to_string(),
-            data: "".to_string(),
+            server_msg: "Test".into(),
+            data: "".into(),
         };
     
         tacacs_connection.add_accounting_reply(&session, 2, &accounting_reply).await?;
@@ -0,0 +1,339 @@
+use async_trait::async_trait;
+use log::info;
+use tacacsrs_messages::authentication::{start::AuthenticationStart, reply::AuthenticationReply, continuation::AuthenticationContinue};
+use tacacsrs_messages::enumerations::{TacacsAuthenticationContinueFlags, TacacsAuthenticationStatus, TacacsType};
+use tacacsrs_messages::body::TacacsBody;
+use tacacsrs_messages::tacacs_string::TacacsString;
+
+use crate::prompter::Prompter;
+use crate::session::{unexpected_body, Session};
+
+/// A request for input sent by the server in a GETUSER, GETPASS or GETDATA reply.
+#[derive(Debug, Clone)]
+pub struct AuthenticationPrompt {
+    pub status: TacacsAuthenticationStatus,
+    pub server_msg: String,
+    pub data: TacacsString,
+    pub no_echo: bool,
+}
+
+impl From<&AuthenticationReply> for AuthenticationPrompt {
+    fn from(reply: &AuthenticationReply) -> Self {
+        AuthenticationPrompt {
+            status: reply.status,
+            server_msg: reply.server_msg.to_string_lossy().into_owned(),
+            data: reply.data.clone(),
+            no_echo: reply.is_no_echo(),
+        }
+    }
+}
+
+/// The client's answer to an `AuthenticationPrompt`.
+#[derive(Debug, Clone)]
+pub enum AuthenticationResponse {
+    Continue(String),
+    Abort(String),
+}
+
+#[async_trait]
+pub trait AuthenticationSessionTrait {
+    async fn send_authentication_start(&self, start: AuthenticationStart) -> anyhow::Result<AuthenticationReply>;
+    async fn send_authentication_continue(&self, continuation: AuthenticationContinue) -> anyhow::Result<AuthenticationReply>;
+    async fn abort_authentication(&self, reason: &str) -> anyhow::Result<()>;
+
+    /// Drives a complete authentication exchange. The prompter is asked for every
+    /// GETUSER, GETPASS or GETDATA reply, and the final PASS, FAIL, ERROR, RESTART or
+    /// FOLLOW reply is returned to the caller.
+    async fn authenticate<P>(&self, start: AuthenticationStart, prompter: &P) -> anyhow::Result<AuthenticationReply>
+        where P: Prompter + ?Sized;
+}
+
+fn is_final_status(status: TacacsAuthenticationStatus) -> bool {
+    !matches!(status,
+        TacacsAuthenticationStatus::TacPlusAuthenStatusGetuser |
+        TacacsAuthenticationStatus::TacPlusAuthenStatusGetpass |
+        TacacsAuthenticationStatus::TacPlusAuthenStatusGetdata)
+}
+
+impl Session {
+    async fn receive_authentication_reply(&self) -> anyhow::Result<AuthenticationReply> {
+        let reply = match self.receive().await?.decode_body()? {
+            TacacsBody::AuthenticationReply(reply) => reply,
+            body => return Err(unexpected_body("an Authentication Reply", &body)),
+        };
+
+        info!(
+            target: "tacacsrs_networking::sessions::authentication_session",
+            "Received Authentication Reply with status {} for session {}",
+            reply.status, self.session_id()
+        );
+
+        if is_final_status(reply.status) {
+            self.complete().await;
+        }
+
+        Ok(reply)
+    }
+}
+
+#[async_trait]
+impl AuthenticationSessionTrait for Session {
+    async fn send_authentication_start(&self, start: AuthenticationStart) -> anyhow::Result<AuthenticationReply>
+    {
+        if self.is_complete().await {
+            return Err(anyhow::Error::msg("Session is already complete"));
+        }
+
+        let minor_version = start.minor_version();
+        *self.minor_version.write().await = minor_version;
+
+        let sequence_number = self.send(TacacsType::TacPlusAuthentication, minor_version, &start).await?;
+
+        info!(
+            target: "tacacsrs_networking::sessions::authentication_session",
+            "Sent Authentication Start with sequence number {} for session {}",
+            sequence_number, self.session_id()
+        );
+
+        self.receive_authentication_reply().await
+    }
+
+    async fn send_authentication_continue(&self, continuation: AuthenticationContinue) -> anyhow::Result<AuthenticationReply>
+    {
+        if self.is_complete().await {
+            return Err(anyhow::Error::msg("Session is already complete"));
+        }
+
+        let minor_version = *self.minor_version.read().await;
+        let sequence_number = self.send(TacacsType::TacPlusAuthentication, minor_version, &continuation).await?;
+
+        info!(
+            target: "tacacsrs_networking::sessions::authentication_session",
+            "Sent Authentication Continue with sequence number {} for session {}",
+            sequence_number, self.session_id()
+        );
+
+        self.receive_authentication_reply().await
+    }
+
+    async fn abort_authentication(&self, reason: &str) -> anyhow::Result<()>
+    {
+        if self.is_complete().await {
+            return Err(anyhow::Error::msg("Session is already complete"));
+        }
+
+        // The server does not reply to an abort, the session simply ends.
+        let minor_version = *self.minor_version.read().await;
+        let sequence_number = self.send(TacacsType::TacPlusAuthentication, minor_version, &AuthenticationContinue::abort(reason)).await?;
+
+        self.complete().await;
+
+        info!(
+            target: "tacacsrs_networking::sessions::authentication_session",
+            "Sent Authentication Abort with sequence number {} for session {}. Session now complete",
+            sequence_number, self.session_id()
+        );
+
+        Ok(())
+    }
+
+    async fn authenticate<P>(&self, start: AuthenticationStart, prompter: &P) -> anyhow::Result<AuthenticationReply>
+        where P: Prompter + ?Sized
+    {
+        let mut reply = self.send_authentication_start(start).await?;
+
+        while !is_final_status(reply.status) {
+            let prompt = AuthenticationPrompt::from(&reply);
+
+            // The prompt is deliberately not logged, a NOECHO prompt expects a secret in return.
+            let response = match prompter.prompt(&prompt).await {
+                Ok(response) => response,
+                Err(e) => {
+                    self.abort_authentication("Client was unable to answer the prompt").await?;
+                    return Err(e.context("Prompter failed"));
+                }
+            };
+
+            reply = match response {
+                AuthenticationResponse::Continue(user_msg) => {
+                    self.send_authentication_continue(AuthenticationContinue {
+                        user_msg: user_msg.into(),
+                        data: TacacsString::new(),
+                        flags: TacacsAuthenticationContinueFlags::empty(),
+                    }).await?
+                },
+                AuthenticationResponse::Abort(reason) => {
+                    self.abort_authentication(&reason).await?;
+                    return Err(anyhow::Error::msg(format!("Authentication aborted by client: {}", reason)));
+                }
+            };
+        }
+
+        Ok(reply)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use tacacsrs_messages::enumerations::*;
+    use tacacsrs_messages::packet::PacketTrait;
+
+    use crate::prompter::ScriptedPrompter;
+    use crate::traits::SessionManagementTrait;
+    use test_log::test;
+
+    fn generate_ascii_login() -> AuthenticationStart {
+        AuthenticationStart {
+            action: TacacsAuthenticationAction::TacPlusAuthenLogin,
+            priv_lvl: 1,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeAscii,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcLogin,
+            user: "".into(),
+            port: "tty0".into(),
+            rem_address: "1.1.1.1".into(),
+            data: TacacsString::new(),
+        }
+    }
+
+    fn generate_reply(status: TacacsAuthenticationStatus, flags: TacacsAuthenicationReplyFlags, server_msg: &str) -> AuthenticationReply {
+        AuthenticationReply {
+            status,
+            flags,
+            server_msg: server_msg.into(),
+            data: TacacsString::new(),
+        }
+    }
+
+    #[test(tokio::test)]
+    async fn test_authenticate_ascii_login() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(
+            crate::mock_connection::MockConnection::new()
+        );
+        tacacs_connection.run().await?;
+
+        let session = tacacs_connection.create_session().await?;
+
+        tacacs_connection.add_authentication_reply(&session, 2, &generate_reply(
+            TacacsAuthenticationStatus::TacPlusAuthenStatusGetuser, TacacsAuthenicationReplyFlags::empty(), "Username: ")).await?;
+        tacacs_connection.add_authentication_reply(&session, 4, &generate_reply(
+            TacacsAuthenticationStatus::TacPlusAuthenStatusGetpass, TacacsAuthenicationReplyFlags::TAC_PLUS_AUTHEN_FLAG_NOECHO, "Password: ")).await?;
+        tacacs_connection.add_authentication_reply(&session, 6, &generate_reply(
+            TacacsAuthenticationStatus::TacPlusAuthenStatusPass, TacacsAuthenicationReplyFlags::empty(), "Welcome")).await?;
+
+        let prompter = ScriptedPrompter::with_answers(["admin", "secret"]);
+        let reply = session.authenticate(generate_ascii_login(), &prompter).await?;
+
+        assert_eq!(reply.status, TacacsAuthenticationStatus::TacPlusAuthenStatusPass);
+        assert!(session.is_complete().await);
+
+        let prompts = prompter.prompts();
+        assert_eq!(prompts.len(), 2);
+        assert!(!prompts[0].no_echo);
+        assert!(prompts[1].no_echo);
+        assert_eq!(prompts[1].server_msg, "Password: ");
+
+        let requests = tacacs_connection.get_requests_for_session(session.session_id).await?;
+        assert_eq!(requests.len(), 3, "The number of requests for the session was not as expected");
+
+        let password = AuthenticationContinue::from_bytes(requests.get(&5).unwrap().body())?;
+        assert_eq!(password.user_msg, "secret");
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_authenticate_pap_login_uses_minor_version_one() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(
+            crate::mock_connection::MockConnection::new()
+        );
+        tacacs_connection.run().await?;
+
+        let session = tacacs_connection.create_session().await?;
+
+        tacacs_connection.add_authentication_reply(&session, 2, &generate_reply(
+            TacacsAuthenticationStatus::TacPlusAuthenStatusFail, TacacsAuthenicationReplyFlags::empty(), "")).await?;
+
+        let mut start = generate_ascii_login();
+        start.authen_type = TacacsAuthenticationType::TacPlusAuthenTypePap;
+        start.user = "admin".into();
+        start.data = "secret".into();
+
+        let prompter = ScriptedPrompter::default();
+        let reply = session.authenticate(start, &prompter).await?;
+        assert!(prompter.prompts().is_empty(), "PAP logins should not prompt");
+        assert_eq!(reply.status, TacacsAuthenticationStatus::TacPlusAuthenStatusFail);
+
+        let requests = tacacs_connection.get_requests_for_session(session.session_id).await?;
+        assert_eq!(requests.get(&1).unwrap().header().minor_version, TacacsMinorVersion::TacacsPlusMinorVerOne);
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_authenticate_abort() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(
+            crate::mock_connection::MockConnection::new()
+        );
+        tacacs_connection.run().await?;
+
+        let session = tacacs_connection.create_session().await?;
+
+        tacacs_connection.add_authentication_reply(&session, 2, &generate_reply(
+            TacacsAuthenticationStatus::TacPlusAuthenStatusGetuser, TacacsAuthenicationReplyFlags::empty(), "Username: ")).await?;
+
+        let prompter = |_: &AuthenticationPrompt| AuthenticationResponse::Abort("cancelled".to_string());
+        let result = session.authenticate(generate_ascii_login(), &prompter).await;
+
+        let error = result.unwrap_err();
+        assert!(error.to_string().contains("Authentication aborted by client"), "Actual Error: {}", error);
+        assert!(session.is_complete().await);
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_authenticate_prompter_failure_aborts() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(
+            crate::mock_connection::MockConnection::new()
+        );
+        tacacs_connection.run().await?;
+
+        let session = tacacs_connection.create_session().await?;
+
+        tacacs_connection.add_authentication_reply(&session, 2, &generate_reply(
+            TacacsAuthenticationStatus::TacPlusAuthenStatusGetuser, TacacsAuthenicationReplyFlags::empty(), "Username: ")).await?;
+
+        let result = session.authenticate(generate_ascii_login(), &ScriptedPrompter::default()).await;
+
+        let error = result.unwrap_err();
+        assert!(error.to_string().contains("Prompter failed"), "Actual Error: {}", error);
+        assert!(session.is_complete().await);
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_continue_on_complete_session_fails() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(
+            crate::mock_connection::MockConnection::new()
+        );
+        tacacs_connection.run().await?;
+
+        let session = tacacs_connection.create_session().await?;
+        session.complete().await;
+
+        let result = session.send_authentication_continue(AuthenticationContinue {
+            user_msg: "admin".into(),
+            data: TacacsString::new(),
+            flags: TacacsAuthenticationContinueFlags::empty(),
+        }).await;
+
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
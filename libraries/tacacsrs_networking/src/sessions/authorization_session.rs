@@ -0,0 +1,215 @@
+use async_trait::async_trait;
+use log::info;
+use tacacsrs_messages::authorization::{request::AuthorizationRequest, reply::AuthorizationReply};
+use tacacsrs_messages::body::TacacsBody;
+use tacacsrs_messages::enumerations::{TacacsAuthorizationStatus, TacacsMinorVersion, TacacsType};
+use tacacsrs_messages::arguments::argument::ArgumentList;
+
+use crate::session::{unexpected_body, Session};
+
+/// The server's decision on an authorization request, keyed on the reply status.
+#[derive(Debug, Clone)]
+pub enum AuthorizationResult {
+    PassAdd(AuthorizationReply),
+    PassRepl(AuthorizationReply),
+    Fail(AuthorizationReply),
+    Error(AuthorizationReply),
+    Follow(AuthorizationReply),
+}
+
+impl From<AuthorizationReply> for AuthorizationResult {
+    fn from(reply: AuthorizationReply) -> Self {
+        match reply.status {
+            TacacsAuthorizationStatus::TacPlusPassAdd => AuthorizationResult::PassAdd(reply),
+            TacacsAuthorizationStatus::TacPlusPassRepl => AuthorizationResult::PassRepl(reply),
+            TacacsAuthorizationStatus::TacPlusFail => AuthorizationResult::Fail(reply),
+            TacacsAuthorizationStatus::TacPlusError => AuthorizationResult::Error(reply),
+            TacacsAuthorizationStatus::TacPlusFollow => AuthorizationResult::Follow(reply),
+        }
+    }
+}
+
+impl AuthorizationResult {
+    pub fn reply(&self) -> &AuthorizationReply {
+        match self {
+            AuthorizationResult::PassAdd(reply) |
+            AuthorizationResult::PassRepl(reply) |
+            AuthorizationResult::Fail(reply) |
+            AuthorizationResult::Error(reply) |
+            AuthorizationResult::Follow(reply) => reply,
+        }
+    }
+
+    pub fn is_permitted(&self) -> bool {
+        matches!(self, AuthorizationResult::PassAdd(_) | AuthorizationResult::PassRepl(_))
+    }
+
+    /// The arguments returned by the server.
+    pub fn args(&self) -> &ArgumentList {
+        &self.reply().args
+    }
+
+    /// The arguments the client should apply. PASS_ADD appends the returned
+    /// arguments to those requested, PASS_REPL replaces them outright, and
+    /// any other status authorizes nothing.
+    pub fn effective_args(&self, requested: &ArgumentList) -> ArgumentList {
+        match self {
+            AuthorizationResult::PassAdd(reply) => requested.iter().chain(reply.args.iter()).cloned().collect(),
+            AuthorizationResult::PassRepl(reply) => reply.args.clone(),
+            _ => ArgumentList::new(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait AuthorizationSessionTrait {
+    async fn send_authorization_request(&self, request: AuthorizationRequest) -> anyhow::Result<AuthorizationResult>;
+}
+
+#[async_trait]
+impl AuthorizationSessionTrait for Session {
+    async fn send_authorization_request(&self, request: AuthorizationRequest) -> anyhow::Result<AuthorizationResult>
+    {
+        if self.is_complete().await {
+            return Err(anyhow::Error::msg("Session is already complete"));
+        }
+
+        let sequence_number = self.send(TacacsType::TacPlusAuthorisation, TacacsMinorVersion::TacacsPlusMinorVerDefault, &request).await?;
+
+        info!(
+            target: "tacacsrs_networking::sessions::authorization_session",
+            "Sent Authorization Request with sequence number {} for session {}",
+            sequence_number, self.session_id()
+        );
+
+        let reply = match self.receive().await?.decode_body()? {
+            TacacsBody::AuthorizationReply(reply) => reply,
+            body => return Err(unexpected_body("an Authorization Reply", &body)),
+        };
+
+        self.complete().await;
+
+        info!(
+            target: "tacacsrs_networking::sessions::authorization_session",
+            "Received Authorization Reply with status {}. Session now complete",
+            reply.status
+        );
+
+        Ok(reply.into())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use tacacsrs_messages::enumerations::*;
+    use tacacsrs_messages::arguments::argument::Argument;
+
+    use crate::traits::SessionManagementTrait;
+    use test_log::test;
+
+    fn generate_authorization_request() -> AuthorizationRequest {
+        AuthorizationRequest {
+            authen_method: TacacsAuthenticationMethod::TacPlusAuthenMethodTacacsplus,
+            priv_lvl: 1,
+            authen_type: TacacsAuthenticationType::TacPlusAuthenTypeAscii,
+            authen_service: TacacsAuthenticationService::TacPlusAuthenSvcLogin,
+            user: "admin".into(),
+            port: "tty0".into(),
+            rem_address: "1.1.1.1".into(),
+            args: vec![
+                Argument::mandatory("service", "shell"),
+                Argument::mandatory("cmd", "show"),
+            ].into(),
+        }
+    }
+
+    fn generate_reply(status: TacacsAuthorizationStatus, args: &[&str]) -> AuthorizationReply {
+        AuthorizationReply {
+            status,
+            args: args.iter().map(|arg| Argument::from_bytes(arg.as_bytes())).collect(),
+            server_msg: "".into(),
+            data: "".into(),
+        }
+    }
+
+    fn to_strings(args: &ArgumentList) -> Vec<String> {
+        args.iter().map(Argument::to_string).collect()
+    }
+
+    #[test(tokio::test)]
+    async fn test_send_authorization_request_pass_add() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(
+            crate::mock_connection::MockConnection::new()
+        );
+        tacacs_connection.run().await?;
+
+        let session = tacacs_connection.create_session().await?;
+
+        tacacs_connection.add_authorization_reply(&session, 2, &generate_reply(
+            TacacsAuthorizationStatus::TacPlusPassAdd, &["priv-lvl=15"])).await?;
+
+        let request = generate_authorization_request();
+        let requested_args = request.args.clone();
+        let result = session.send_authorization_request(request).await?;
+
+        assert!(matches!(result, AuthorizationResult::PassAdd(_)));
+        assert!(result.is_permitted());
+        assert_eq!(to_strings(result.args()), ["priv-lvl=15"]);
+        assert_eq!(to_strings(&result.effective_args(&requested_args)), vec!["service=shell", "cmd=show", "priv-lvl=15"]);
+        assert!(session.is_complete().await);
+
+        let requests = tacacs_connection.get_requests_for_session(session.session_id).await?;
+        assert_eq!(requests.len(), 1, "The number of requests for the session was not as expected");
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_send_authorization_request_pass_repl() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(
+            crate::mock_connection::MockConnection::new()
+        );
+        tacacs_connection.run().await?;
+
+        let session = tacacs_connection.create_session().await?;
+
+        tacacs_connection.add_authorization_reply(&session, 2, &generate_reply(
+            TacacsAuthorizationStatus::TacPlusPassRepl, &["service=shell", "cmd=show", "cmd-arg=version"])).await?;
+
+        let request = generate_authorization_request();
+        let requested_args = request.args.clone();
+        let result = session.send_authorization_request(request).await?;
+
+        assert!(matches!(result, AuthorizationResult::PassRepl(_)));
+        assert_eq!(to_strings(&result.effective_args(&requested_args)), vec!["service=shell", "cmd=show", "cmd-arg=version"]);
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_send_authorization_request_fail() -> anyhow::Result<()> {
+        let tacacs_connection = Arc::new(
+            crate::mock_connection::MockConnection::new()
+        );
+        tacacs_connection.run().await?;
+
+        let session = tacacs_connection.create_session().await?;
+
+        tacacs_connection.add_authorization_reply(&session, 2, &generate_reply(
+            TacacsAuthorizationStatus::TacPlusFail, &[])).await?;
+
+        let request = generate_authorization_request();
+        let requested_args = request.args.clone();
+        let result = session.send_authorization_request(request).await?;
+
+        assert!(matches!(result, AuthorizationResult::Fail(_)));
+        assert!(!result.is_permitted());
+        assert!(result.effective_args(&requested_args).is_empty());
+
+        Ok(())
+    }
+}
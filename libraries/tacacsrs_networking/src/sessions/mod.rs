@@ -1 +1,3 @@
-pub mod accounting_session;
\ No newline at end of file
+pub mod accounting_session;
+pub mod authentication_session;
+pub mod authorization_session;
\ No newline at end of file
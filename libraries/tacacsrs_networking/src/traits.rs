@@ -1,10 +1,27 @@
 use async_trait::async_trait;
-use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::events::{ConnectionEvent, ConnectionState};
 use crate::session::Session;
+use crate::session_manager::SingleConnectState;
 
 
+/// Implemented by every connection, so applications can hold an
+/// `Arc<dyn SessionManagementTrait>` whatever the transport.
 #[async_trait]
-pub trait SessionManagementTrait {
-    async fn can_create_sessions(self : &Arc<Self>) -> bool;
-    async fn create_session(self : &Arc<Self>) -> anyhow::Result<Session>;
-}
\ No newline at end of file
+pub trait SessionManagementTrait : Send + Sync {
+    async fn can_create_sessions(&self) -> bool;
+    async fn create_session(&self) -> anyhow::Result<Session>;
+
+    /// The ids of sessions that have not yet completed or been dropped.
+    fn active_session_ids(&self) -> Vec<u32>;
+    fn active_session_count(&self) -> usize;
+
+    /// Whether the server has agreed to carry several sessions on this connection.
+    fn single_connect(&self) -> SingleConnectState;
+
+    fn state(&self) -> ConnectionState;
+
+    /// Events published on the connection from now on.
+    fn subscribe(&self) -> broadcast::Receiver<ConnectionEvent>;
+}